@@ -1,41 +1,277 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
+use crate::schema::Position;
+
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("duplicate command {0}")]
-    DuplicateCommand(String),
-    #[error("duplicate custom type {0}")]
-    DuplicateCustomType(String),
-    #[error("duplicate field {field} in custom type {ty}")]
-    DuplicateCustomTypeField { ty: String, field: String },
-    #[error("duplicate event {0}")]
-    DuplicateEvent(String),
-    #[error("duplicate field {field} in event {event}")]
-    DuplicateEventField { event: String, field: String },
-    #[error("duplicate param {param} in {command}")]
-    DuplicateParam { command: String, param: String },
-    #[error("duplicate field {field} in type {ty}")]
-    DuplicateTypeField { ty: String, field: String },
-    #[error("event not defined {0}")]
-    EventNotDefined(String),
+    #[error("aggregate not defined {name} at {position}")]
+    AggregateNotDefined { name: String, position: Position },
+    #[error("{name} at {position} is declared in more than one package")]
+    AmbiguousName { name: String, position: Position },
+    #[error("command {command} not defined in aggregate {aggregate} at {position}")]
+    CommandNotDefined {
+        aggregate: String,
+        command: String,
+        position: Position,
+    },
+    #[error("alias {name} at {position} refers to itself")]
+    CyclicAlias { name: String, position: Position },
+    #[error("import of {} at {position} forms a cycle", path.display())]
+    CyclicImport { path: PathBuf, position: Position },
+    #[error("default for {name} at {position} violates constraint @{constraint}")]
+    DefaultViolatesConstraint {
+        name: String,
+        constraint: String,
+        position: Position,
+    },
+    #[error("duplicate aggregate {name} at {position}")]
+    DuplicateAggregate { name: String, position: Position },
+    #[error("duplicate command {name} at {position}")]
+    DuplicateCommand { name: String, position: Position },
+    #[error("duplicate error {error} in {command} at {position}")]
+    DuplicateCommandError {
+        command: String,
+        error: String,
+        position: Position,
+    },
+    #[error("duplicate custom type {name} at {position}")]
+    DuplicateCustomType { name: String, position: Position },
+    #[error("duplicate field {field} in custom type {ty} at {position}")]
+    DuplicateCustomTypeField {
+        ty: String,
+        field: String,
+        position: Position,
+    },
+    #[error("duplicate enum {name} at {position}")]
+    DuplicateEnum { name: String, position: Position },
+    #[error("duplicate variant {variant} in enum {ty} at {position}")]
+    DuplicateEnumVariant {
+        ty: String,
+        variant: String,
+        position: Position,
+    },
+    #[error("duplicate error {name} at {position}")]
+    DuplicateError { name: String, position: Position },
+    #[error("duplicate field {field} in error {error} at {position}")]
+    DuplicateErrorField {
+        error: String,
+        field: String,
+        position: Position,
+    },
+    #[error("duplicate event {name} at {position}")]
+    DuplicateEvent { name: String, position: Position },
+    #[error("duplicate field {field} in event {event} at {position}")]
+    DuplicateEventField {
+        event: String,
+        field: String,
+        position: Position,
+    },
+    #[error("{name} imported from {} at {position} is already declared", path.display())]
+    DuplicateImport {
+        name: String,
+        path: PathBuf,
+        position: Position,
+    },
+    #[error("duplicate param {param} in {command} at {position}")]
+    DuplicateParam {
+        command: String,
+        param: String,
+        position: Position,
+    },
+    #[error("duplicate projection {name} at {position}")]
+    DuplicateProjection { name: String, position: Position },
+    #[error("duplicate field {field} in projection {projection} at {position}")]
+    DuplicateProjectionField {
+        projection: String,
+        field: String,
+        position: Position,
+    },
+    #[error("duplicate saga {name} at {position}")]
+    DuplicateSaga { name: String, position: Position },
+    #[error("duplicate scalar {name} at {position}")]
+    DuplicateScalar { name: String, position: Position },
+    #[error("duplicate state in aggregate {aggregate} at {position}")]
+    DuplicateState {
+        aggregate: String,
+        position: Position,
+    },
+    #[error("duplicate field {field} in state of aggregate {aggregate} at {position}")]
+    DuplicateStateField {
+        aggregate: String,
+        field: String,
+        position: Position,
+    },
+    #[error("duplicate subscription to {event} in projection {projection} at {position}")]
+    DuplicateSubscription {
+        projection: String,
+        event: String,
+        position: Position,
+    },
+    #[error("duplicate type param {param} in custom type {ty} at {position}")]
+    DuplicateTypeParam {
+        ty: String,
+        param: String,
+        position: Position,
+    },
+    #[error("duplicate variant {variant} in union {ty} at {position}")]
+    DuplicateUnionVariant {
+        ty: String,
+        variant: String,
+        position: Position,
+    },
+    #[error("duplicate field {field} in variant {variant} of union {ty} at {position}")]
+    DuplicateUnionVariantField {
+        ty: String,
+        variant: String,
+        field: String,
+        position: Position,
+    },
+    #[error("projection {name} at {position} does not subscribe to any events")]
+    EmptyProjection { name: String, position: Position },
+    #[error("saga {name} at {position} does not react to any events")]
+    EmptySaga { name: String, position: Position },
+    #[error("error not defined {name} at {position}")]
+    ErrorNotDefined { name: String, position: Position },
+    #[error("event not defined {name} at {position}")]
+    EventNotDefined { name: String, position: Position },
+    #[error("{}: {source}", path.display())]
+    File { path: PathBuf, source: Box<Error> },
+    #[error("invalid argument to annotation @{name} at {position}")]
+    InvalidAnnotationArgument { name: String, position: Position },
+    #[error("constraint @{name} at {position} does not apply to {ty}")]
+    InvalidConstraint {
+        name: String,
+        ty: String,
+        position: Position,
+    },
+    #[error("invalid argument to constraint @{name} at {position}")]
+    InvalidConstraintArgument { name: String, position: Position },
+    #[error("invalid default for {name} at {position}, expected {ty}")]
+    InvalidDefault {
+        name: String,
+        ty: String,
+        position: Position,
+    },
+    #[error("invalid identity type {ty} of aggregate {aggregate} at {position}, expected String, Int, UInt, Long, ULong, Uuid or a custom scalar")]
+    InvalidIdentity {
+        aggregate: String,
+        ty: String,
+        position: Position,
+    },
+    #[error("invalid map key {name} at {position}, expected scalar, custom scalar or enum")]
+    InvalidMapKey { name: String, position: Position },
+    #[error("type {name} at {position} expects {expected} type arguments, found {found}")]
+    InvalidTypeArguments {
+        name: String,
+        expected: usize,
+        found: usize,
+        position: Position,
+    },
     #[error(transparent)]
     Io(#[from] std::io::Error),
-    #[error("missing aggregate")]
-    MissingAggregate,
     #[error("missing version")]
     MissingVersion,
-    #[error("multiple aggregates")]
-    MultipleAggregates,
+    #[error("multiple packages")]
+    MultiplePackages,
     #[error("multiple versions")]
     MultipleVersions,
     #[error("parse error: {0}")]
     Parse(String),
-    #[error("type not defined {0}")]
-    TypeNotDefined(String),
+    #[error("type not defined {name} at {position}")]
+    TypeNotDefined { name: String, position: Position },
+    #[error("type {name} at {position} can never be constructed, as it requires itself")]
+    UnbuildableType { name: String, position: Position },
+    #[error("unknown constraint @{name} at {position}")]
+    UnknownConstraint { name: String, position: Position },
+    #[error("{name} at {position} is not declared in {}", path.display())]
+    UnresolvedImport {
+        name: String,
+        path: PathBuf,
+        position: Position,
+    },
+    #[error("import at {position} requires the schema to be loaded with Schema::from_file")]
+    UnsupportedImport { position: Position },
+    #[error("type argument {argument} of {name} at {position} is not supported, expected a single required type")]
+    UnsupportedTypeArgument {
+        name: String,
+        argument: String,
+        position: Position,
+    },
+}
+
+impl Error {
+    /// Position in the schema source the error refers to, if any.
+    ///
+    /// For errors in an imported file, the position is within that file, see [`Error::File`].
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            Error::AggregateNotDefined { position, .. }
+            | Error::AmbiguousName { position, .. }
+            | Error::CommandNotDefined { position, .. }
+            | Error::CyclicAlias { position, .. }
+            | Error::CyclicImport { position, .. }
+            | Error::DefaultViolatesConstraint { position, .. }
+            | Error::DuplicateAggregate { position, .. }
+            | Error::DuplicateCommand { position, .. }
+            | Error::DuplicateCommandError { position, .. }
+            | Error::DuplicateCustomType { position, .. }
+            | Error::DuplicateCustomTypeField { position, .. }
+            | Error::DuplicateEnum { position, .. }
+            | Error::DuplicateEnumVariant { position, .. }
+            | Error::DuplicateError { position, .. }
+            | Error::DuplicateErrorField { position, .. }
+            | Error::DuplicateEvent { position, .. }
+            | Error::DuplicateEventField { position, .. }
+            | Error::DuplicateImport { position, .. }
+            | Error::DuplicateParam { position, .. }
+            | Error::DuplicateProjection { position, .. }
+            | Error::DuplicateProjectionField { position, .. }
+            | Error::DuplicateSaga { position, .. }
+            | Error::DuplicateScalar { position, .. }
+            | Error::DuplicateState { position, .. }
+            | Error::DuplicateStateField { position, .. }
+            | Error::DuplicateSubscription { position, .. }
+            | Error::DuplicateTypeParam { position, .. }
+            | Error::DuplicateUnionVariant { position, .. }
+            | Error::DuplicateUnionVariantField { position, .. }
+            | Error::EmptyProjection { position, .. }
+            | Error::EmptySaga { position, .. }
+            | Error::ErrorNotDefined { position, .. }
+            | Error::EventNotDefined { position, .. }
+            | Error::InvalidAnnotationArgument { position, .. }
+            | Error::InvalidConstraint { position, .. }
+            | Error::InvalidConstraintArgument { position, .. }
+            | Error::InvalidDefault { position, .. }
+            | Error::InvalidIdentity { position, .. }
+            | Error::InvalidMapKey { position, .. }
+            | Error::InvalidTypeArguments { position, .. }
+            | Error::TypeNotDefined { position, .. }
+            | Error::UnbuildableType { position, .. }
+            | Error::UnknownConstraint { position, .. }
+            | Error::UnresolvedImport { position, .. }
+            | Error::UnsupportedImport { position }
+            | Error::UnsupportedTypeArgument { position, .. } => Some(*position),
+            Error::File { source, .. } => source.position(),
+            Error::Io(_)
+            | Error::MissingVersion
+            | Error::MultiplePackages
+            | Error::MultipleVersions
+            | Error::Parse(_) => None,
+        }
+    }
+}
+
+impl From<nom_supreme::error::ErrorTree<Position>> for Error {
+    fn from(err: nom_supreme::error::ErrorTree<Position>) -> Self {
+        Error::Parse(err.to_string())
+    }
 }
 
-impl From<nom_supreme::error::ErrorTree<&str>> for Error {
-    fn from(err: nom_supreme::error::ErrorTree<&str>) -> Self {
+#[cfg(feature = "pest")]
+impl From<pest::error::Error<crate::parser::pest::Rule>> for Error {
+    fn from(err: pest::error::Error<crate::parser::pest::Rule>) -> Self {
         Error::Parse(err.to_string())
     }
 }
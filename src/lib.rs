@@ -11,9 +11,20 @@ pub(crate) mod parser;
 pub mod schema;
 
 pub use error::Error;
+#[cfg(feature = "pest")]
+pub use parser::pest::{Rule, SchemaParser};
 
 /// Parse and validate an ESDL schema string.
 pub fn parse(input: &str) -> Result<schema::Schema, Error> {
     let schema = parser::parse(input)?;
     schema::Schema::validate_parsed_schema(schema)
 }
+
+/// Parse and validate an ESDL schema string with the pest grammar in `schema.pest`.
+///
+/// Accepts and produces exactly the same schemas as [`parse`].
+#[cfg(feature = "pest")]
+pub fn parse_with_pest(input: &str) -> Result<schema::Schema, Error> {
+    let schema = parser::pest::parse(input)?;
+    schema::Schema::validate_parsed_schema(schema)
+}
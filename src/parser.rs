@@ -1,14 +1,28 @@
-use nom_supreme::{error::ErrorTree, final_parser::final_parser};
+use nom_locate::LocatedSpan;
+use nom_supreme::{
+    error::ErrorTree,
+    final_parser::{final_parser, RecreateContext},
+};
+
+use crate::schema::Position;
 
 pub mod aggregate;
+pub mod annotation;
 pub mod event;
 pub mod ident;
+pub mod import;
+pub mod literal;
+pub mod package;
 pub mod parsers;
+#[cfg(feature = "pest")]
+pub mod pest;
+pub mod projection;
+pub mod saga;
 pub mod schema;
 pub mod types;
 pub mod version;
 
-pub type Span<'i> = &'i str;
+pub type Span<'i> = LocatedSpan<&'i str>;
 pub type Error<I> = ErrorTree<I>;
 pub type IResult<I, O, E = Error<I>> = Result<(I, O), nom::Err<E>>;
 
@@ -16,10 +30,16 @@ use crate::parser::schema::parse_schema;
 
 use self::schema::Schema;
 
-pub fn parse<'i>(input: impl Into<Span<'i>>) -> Result<Schema<'i>, Error<Span<'i>>> {
+pub fn parse<'i>(input: impl Into<Span<'i>>) -> Result<Schema<'i>, Error<Position>> {
     final_parser(parse_schema)(input.into())
 }
 
+impl<'i> RecreateContext<Span<'i>> for Position {
+    fn recreate_context(_original_input: Span<'i>, tail: Span<'i>) -> Self {
+        Position::from(tail)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use semver::{BuildMetadata, Prerelease, Version};
@@ -29,12 +49,26 @@ mod tests {
         event::{Event, Field},
         parse,
         schema::Schema,
-        types::{OptionalOrRequiredType, Scalar, ScalarOrUserType, Type},
+        types::{
+            Alias, Enum, EnumVariant, OptionalOrRequiredType, Scalar, ScalarOrUserType, Type,
+            Union, UnionVariant,
+        },
         Error, Span,
     };
+    use crate::schema::Position;
+
+    /// Locates the `nth` occurrence of `needle` within `input`.
+    fn span<'i>(input: &'i str, needle: &str, nth: usize) -> Span<'i> {
+        let offset = input
+            .match_indices(needle)
+            .nth(nth)
+            .map(|(offset, _)| offset)
+            .unwrap();
+        nom::Slice::slice(&Span::new(input), offset..offset + needle.len())
+    }
 
     #[test]
-    fn it_parses_basic_schema() -> Result<(), Error<Span<'static>>> {
+    fn it_parses_basic_schema() -> Result<(), Error<Position>> {
         let schema_str = r#"
             version = "0.1.0"
 
@@ -49,31 +83,59 @@ mod tests {
 
         let expected = Schema {
             versions: vec![Version::new(0, 1, 0)],
+            packages: vec![],
+            imports: vec![],
+            projections: vec![],
+            sagas: vec![],
             aggregates: vec![Aggregate {
-                ident: "Hello",
+                docs: vec![],
+                annotations: vec![],
+                ident: span(schema_str, "Hello", 0),
+                identity: None,
+                states: vec![],
                 commands: vec![Command {
-                    ident: "world",
+                    docs: vec![],
+                    annotations: vec![],
+                    ident: span(schema_str, "world", 0),
                     params: vec![Param {
-                        ident: "name",
+                        docs: vec![],
+                        annotations: vec![],
+                        ident: span(schema_str, "name", 0),
                         ty: Type::Single(OptionalOrRequiredType::Required(
                             ScalarOrUserType::Scalar(Scalar::String),
                         )),
+                        constraints: vec![],
+                        default: None,
                     }],
-                    return_type: ReturnType::Single(ReturnTypeOptionalOrRequired::Required(
-                        "FooEvent",
-                    )),
+                    return_type: ReturnType::Single(ReturnTypeOptionalOrRequired::Required(span(
+                        schema_str, "FooEvent", 0,
+                    ))),
+                    errors: vec![],
                 }],
             }],
             events: vec![Event {
-                ident: "FooEvent",
+                docs: vec![],
+                annotations: vec![],
+                ident: span(schema_str, "FooEvent", 1),
                 fields: vec![Field {
-                    ident: "name",
+                    docs: vec![],
+                    annotations: vec![],
+                    ident: span(schema_str, "name", 1),
                     ty: Type::Single(OptionalOrRequiredType::Required(ScalarOrUserType::Scalar(
                         Scalar::String,
                     ))),
+                    constraints: vec![],
+                    default: None,
                 }],
+                package: None,
+                file: None,
             }],
+            errors: vec![],
             types: vec![],
+            unions: vec![],
+            aliases: vec![],
+            enums: vec![],
+            scalars: vec![],
         };
 
         assert_eq!(parse(schema_str)?, expected);
@@ -82,7 +144,7 @@ mod tests {
     }
 
     #[test]
-    fn it_parses_bank_account_schema() -> Result<(), Error<Span<'static>>> {
+    fn it_parses_bank_account_schema() -> Result<(), Error<Position>> {
         let schema_str = r#"
           version = "10.2.4-alpha"
 
@@ -116,97 +178,463 @@ mod tests {
 
         let expected = Schema {
             versions: vec![version],
+            packages: vec![],
+            imports: vec![],
+            projections: vec![],
+            sagas: vec![],
             aggregates: vec![Aggregate {
-                ident: "BankAccount",
+                docs: vec![],
+                annotations: vec![],
+                ident: span(schema_str, "BankAccount", 0),
+                identity: None,
+                states: vec![],
                 commands: vec![
                     Command {
-                        ident: "open_account",
+                        docs: vec![],
+                        annotations: vec![],
+                        ident: span(schema_str, "open_account", 0),
                         params: vec![
                             Param {
-                                ident: "name",
+                                docs: vec![],
+                                annotations: vec![],
+                                ident: span(schema_str, "name", 0),
                                 ty: Type::Single(OptionalOrRequiredType::Optional(
                                     ScalarOrUserType::Scalar(Scalar::String),
                                 )),
+                                constraints: vec![],
+                                default: None,
                             },
                             Param {
-                                ident: "initial_balance",
+                                docs: vec![],
+                                annotations: vec![],
+                                ident: span(schema_str, "initial_balance", 0),
                                 ty: Type::Single(OptionalOrRequiredType::Required(
                                     ScalarOrUserType::Scalar(Scalar::Float),
                                 )),
+                                constraints: vec![],
+                                default: None,
                             },
                         ],
                         return_type: ReturnType::Single(ReturnTypeOptionalOrRequired::Optional(
-                            "OpenedAccount",
+                            span(schema_str, "OpenedAccount", 0),
                         )),
+                        errors: vec![],
                     },
                     Command {
-                        ident: "deposit_funds",
+                        docs: vec![],
+                        annotations: vec![],
+                        ident: span(schema_str, "deposit_funds", 0),
                         params: vec![Param {
-                            ident: "amount",
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "amount", 0),
                             ty: Type::Single(OptionalOrRequiredType::Required(
                                 ScalarOrUserType::Scalar(Scalar::Float),
                             )),
+                            constraints: vec![],
+                            default: None,
                         }],
                         return_type: ReturnType::Single(ReturnTypeOptionalOrRequired::Optional(
-                            "DepositedFunds",
+                            span(schema_str, "DepositedFunds", 0),
                         )),
+                        errors: vec![],
                     },
                     Command {
-                        ident: "withdraw_funds",
+                        docs: vec![],
+                        annotations: vec![],
+                        ident: span(schema_str, "withdraw_funds", 0),
                         params: vec![Param {
-                            ident: "amount",
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "amount", 1),
                             ty: Type::Single(OptionalOrRequiredType::Required(
                                 ScalarOrUserType::Scalar(Scalar::Float),
                             )),
+                            constraints: vec![],
+                            default: None,
                         }],
                         return_type: ReturnType::Single(ReturnTypeOptionalOrRequired::Optional(
-                            "WithdrewFunds",
+                            span(schema_str, "WithdrewFunds", 0),
                         )),
+                        errors: vec![],
                     },
                 ],
             }],
             events: vec![
                 Event {
-                    ident: "OpenedAccount",
+                    docs: vec![],
+                    annotations: vec![],
+                    ident: span(schema_str, "OpenedAccount", 1),
                     fields: vec![
                         Field {
-                            ident: "name",
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "name", 1),
                             ty: Type::Single(OptionalOrRequiredType::Optional(
                                 ScalarOrUserType::Scalar(Scalar::String),
                             )),
+                            constraints: vec![],
+                            default: None,
                         },
                         Field {
-                            ident: "initial_balance",
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "initial_balance", 1),
                             ty: Type::Single(OptionalOrRequiredType::Required(
                                 ScalarOrUserType::Scalar(Scalar::Float),
                             )),
+                            constraints: vec![],
+                            default: None,
                         },
                     ],
+                    package: None,
+                    file: None,
                 },
                 Event {
-                    ident: "DepositedFunds",
+                    docs: vec![],
+                    annotations: vec![],
+                    ident: span(schema_str, "DepositedFunds", 1),
                     fields: vec![Field {
-                        ident: "amount",
+                        docs: vec![],
+                        annotations: vec![],
+                        ident: span(schema_str, "amount", 2),
                         ty: Type::Single(OptionalOrRequiredType::Required(
                             ScalarOrUserType::Scalar(Scalar::Float),
                         )),
+                        constraints: vec![],
+                        default: None,
                     }],
+                    package: None,
+                    file: None,
                 },
                 Event {
-                    ident: "WithdrewFunds",
+                    docs: vec![],
+                    annotations: vec![],
+                    ident: span(schema_str, "WithdrewFunds", 1),
                     fields: vec![Field {
-                        ident: "amount",
+                        docs: vec![],
+                        annotations: vec![],
+                        ident: span(schema_str, "amount", 3),
                         ty: Type::Single(OptionalOrRequiredType::Required(
                             ScalarOrUserType::Scalar(Scalar::Float),
                         )),
+                        constraints: vec![],
+                        default: None,
                     }],
+                    package: None,
+                    file: None,
                 },
             ],
+            errors: vec![],
             types: vec![],
+            unions: vec![],
+            aliases: vec![],
+            enums: vec![],
+            scalars: vec![],
         };
 
         assert_eq!(parse(schema_str)?, expected);
 
         Ok(())
     }
+
+    #[test]
+    fn it_parses_comments() -> Result<(), Error<Position>> {
+        let schema_str = r#"
+            // Schema version
+            version = "0.1.0" /* inline */
+
+            /*
+             * Multi-line block comment
+             */
+            aggregate Hello { // trailing comment
+                // Comment between commands
+                world(name: String /* param comment */) -> FooEvent // trailing comment
+
+                /* Block comment between commands */
+                goodbye() -> FooEvent
+            }
+
+            // Comment between items
+            event /* keyword comment */ FooEvent {
+                // Comment before field
+                name: String // trailing comment
+                /* Comment after field */
+            }
+
+            type Empty { /* empty */ }
+        "#;
+
+        let schema = parse(schema_str)?;
+        assert_eq!(schema.versions, vec![Version::new(0, 1, 0)]);
+        assert_eq!(schema.aggregates.len(), 1);
+        assert_eq!(schema.aggregates[0].commands.len(), 2);
+        assert_eq!(
+            schema.aggregates[0].commands[0].ident,
+            span(schema_str, "world", 0)
+        );
+        assert_eq!(
+            schema.aggregates[0].commands[1].ident,
+            span(schema_str, "goodbye", 0)
+        );
+        assert_eq!(schema.events.len(), 1);
+        assert_eq!(schema.events[0].fields.len(), 1);
+        assert_eq!(schema.types.len(), 1);
+        assert!(schema.types[0].fields.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_doc_comments() -> Result<(), Error<Position>> {
+        let schema_str = r#"
+            /// Bank account.
+            ///
+            /// Holds funds.
+            aggregate BankAccount {
+                /// Opens an account.
+                open_account(
+                    /// Starting balance.
+                    initial_balance: Float
+                ) -> OpenedAccount
+            }
+
+            /// Account was opened.
+            // Regular comments are not docs.
+            event OpenedAccount {
+                /// Starting balance.
+                initial_balance: Float
+            }
+
+            //// Not a doc comment.
+            type User {
+                name: String
+            }
+        "#;
+
+        let schema = parse(schema_str)?;
+        assert_eq!(
+            schema.aggregates[0]
+                .docs
+                .iter()
+                .map(|line| *line.fragment())
+                .collect::<Vec<_>>(),
+            vec![" Bank account.", "", " Holds funds."]
+        );
+        assert_eq!(
+            schema.aggregates[0].commands[0].docs,
+            vec![span(schema_str, " Opens an account.", 0)]
+        );
+        assert_eq!(
+            schema.aggregates[0].commands[0].params[0].docs,
+            vec![span(schema_str, " Starting balance.", 0)]
+        );
+        assert_eq!(
+            schema.events[0].docs,
+            vec![span(schema_str, " Account was opened.", 0)]
+        );
+        assert_eq!(
+            schema.events[0].fields[0].docs,
+            vec![span(schema_str, " Starting balance.", 1)]
+        );
+        assert!(schema.types[0].docs.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_enums() -> Result<(), Error<Position>> {
+        let schema_str = r#"
+            enum Currency { Aud, Usd, Eur }
+
+            /// Account status.
+            enum Status {
+                Open,
+                /// Account was closed.
+                Closed
+                Frozen,
+            }
+        "#;
+
+        let schema = parse(schema_str)?;
+        assert_eq!(
+            schema.enums,
+            vec![
+                Enum {
+                    docs: vec![],
+                    annotations: vec![],
+                    ident: span(schema_str, "Currency", 0),
+                    variants: vec![
+                        EnumVariant {
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "Aud", 0),
+                        },
+                        EnumVariant {
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "Usd", 0),
+                        },
+                        EnumVariant {
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "Eur", 0),
+                        },
+                    ],
+                    package: None,
+                    file: None,
+                },
+                Enum {
+                    docs: vec![span(schema_str, " Account status.", 0)],
+                    annotations: vec![],
+                    ident: span(schema_str, "Status", 0),
+                    variants: vec![
+                        EnumVariant {
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "Open", 0),
+                        },
+                        EnumVariant {
+                            docs: vec![span(schema_str, " Account was closed.", 0)],
+                            annotations: vec![],
+                            ident: span(schema_str, "Closed", 0),
+                        },
+                        EnumVariant {
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "Frozen", 0),
+                        },
+                    ],
+                    package: None,
+                    file: None,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_unions() -> Result<(), Error<Position>> {
+        let schema_str = r#"
+            type PaymentMethod = Card { last4: String } | BankTransfer { bsb: String, account: String }
+
+            type Payer =
+                | Person {
+                    name: String
+                }
+                | Anonymous {}
+        "#;
+
+        let string_type = || {
+            Type::Single(OptionalOrRequiredType::Required(ScalarOrUserType::Scalar(
+                Scalar::String,
+            )))
+        };
+        let field = |ident| Field {
+            docs: vec![],
+            annotations: vec![],
+            ident: span(schema_str, ident, 0),
+            ty: string_type(),
+            constraints: vec![],
+            default: None,
+        };
+
+        let schema = parse(schema_str)?;
+        assert_eq!(
+            schema.unions,
+            vec![
+                Union {
+                    docs: vec![],
+                    annotations: vec![],
+                    ident: span(schema_str, "PaymentMethod", 0),
+                    variants: vec![
+                        UnionVariant {
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "Card", 0),
+                            fields: vec![field("last4")],
+                        },
+                        UnionVariant {
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "BankTransfer", 0),
+                            fields: vec![field("bsb"), field("account")],
+                        },
+                    ],
+                    package: None,
+                    file: None,
+                },
+                Union {
+                    docs: vec![],
+                    annotations: vec![],
+                    ident: span(schema_str, "Payer", 0),
+                    variants: vec![
+                        UnionVariant {
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "Person", 0),
+                            fields: vec![field("name")],
+                        },
+                        UnionVariant {
+                            docs: vec![],
+                            annotations: vec![],
+                            ident: span(schema_str, "Anonymous", 0),
+                            fields: vec![],
+                        },
+                    ],
+                    package: None,
+                    file: None,
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_structs_and_aliases() -> Result<(), Error<Position>> {
+        let schema_str = r#"
+            struct User {
+                name: String
+            }
+
+            type Money = Float;
+            type Tags = [String]
+        "#;
+
+        let schema = parse(schema_str)?;
+        assert_eq!(schema.types.len(), 1);
+        assert_eq!(schema.types[0].ident, span(schema_str, "User", 0));
+        assert_eq!(
+            schema.aliases,
+            vec![
+                Alias {
+                    docs: vec![],
+                    annotations: vec![],
+                    ident: span(schema_str, "Money", 0),
+                    ty: Type::Single(OptionalOrRequiredType::Required(ScalarOrUserType::Scalar(
+                        Scalar::Float
+                    ))),
+                    package: None,
+                    file: None,
+                },
+                Alias {
+                    docs: vec![],
+                    annotations: vec![],
+                    ident: span(schema_str, "Tags", 0),
+                    ty: Type::Array {
+                        inner: Box::new(Type::Single(OptionalOrRequiredType::Required(
+                            ScalarOrUserType::Scalar(Scalar::String)
+                        ))),
+                        optional: false,
+                    },
+                    package: None,
+                    file: None,
+                },
+            ]
+        );
+
+        Ok(())
+    }
 }
@@ -1,28 +1,49 @@
 use nom::{
     branch::alt,
-    character::complete::{char, multispace0, space0, space1},
-    combinator::{map, opt},
-    multi::separated_list0,
+    character::complete::char,
+    combinator::{map, opt, value},
+    multi::{separated_list0, separated_list1},
     sequence::{delimited, pair, preceded, separated_pair, tuple},
 };
 use nom_supreme::tag::complete::tag;
 
 use super::{
+    annotation::{parse_annotations, parse_constraints, Annotation},
+    event::{parse_field, Field},
     ident::{parse_camel_ident, parse_snake_ident},
-    parsers::delimited_multiline_list0,
+    literal::{parse_default, Literal},
+    parsers::{delimited_list0, multispace_comment0, parse_docs, space_comment0, space_comment1},
     types::{parse_type, Type},
     IResult, Span,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Aggregate<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
     pub ident: Span<'i>,
+    pub identity: Option<Identity<'i>>,
+    /// State blocks, of which a valid aggregate declares at most one.
+    pub states: Vec<State<'i>>,
     pub commands: Vec<Command<'i>>,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum AggregateItem<'i> {
+    State(State<'i>),
+    Command(Command<'i>),
+}
+
 pub fn parse_aggregate(input: Span) -> IResult<Span, Aggregate> {
-    let ident_parser = preceded(pair(tag("aggregate"), space1), parse_camel_ident);
-    let commands_parser = delimited_multiline_list0(|| tag("{"), parse_command, || tag("}"));
+    let ident_parser = preceded(
+        pair(tag("aggregate"), space_comment1),
+        pair(parse_camel_ident, opt(parse_identity)),
+    );
+    let item_parser = alt((
+        map(parse_state, AggregateItem::State),
+        map(parse_command, AggregateItem::Command),
+    ));
+    let commands_parser = delimited_list0(tag("{"), item_parser, tag("}"));
     // let commands_parser = delimited(
     //     tuple((space0, tag("{"), space0, newline, multispace0)),
     //     separated_list0(tuple((multispace0, multispace1, space0)), parse_command),
@@ -30,59 +51,180 @@ pub fn parse_aggregate(input: Span) -> IResult<Span, Aggregate> {
     // );
 
     map(
-        separated_pair(ident_parser, multispace0, commands_parser),
-        |(ident, commands)| Aggregate { ident, commands },
+        tuple((
+            parse_docs,
+            parse_annotations,
+            separated_pair(ident_parser, multispace_comment0, commands_parser),
+        )),
+        |(docs, annotations, ((ident, identity), items))| {
+            let mut states = Vec::new();
+            let mut commands = Vec::new();
+            for item in items {
+                match item {
+                    AggregateItem::State(state) => states.push(state),
+                    AggregateItem::Command(command) => commands.push(command),
+                }
+            }
+
+            Aggregate {
+                docs,
+                annotations,
+                ident,
+                identity,
+                states,
+                commands,
+            }
+        },
+    )(input)
+}
+
+/// Key identifying each instance of an aggregate.
+/// - `aggregate BankAccount(id: Uuid)`
+/// - `aggregate BankAccount keyed by account_number: String`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Identity<'i> {
+    pub ident: Span<'i>,
+    pub ty: Type<'i>,
+}
+
+pub fn parse_identity(input: Span) -> IResult<Span, Identity> {
+    let key_parser = || {
+        separated_pair(
+            parse_snake_ident,
+            tuple((space_comment0, tag(":"), space_comment0)),
+            parse_type,
+        )
+    };
+    let parenthesized_parser = delimited(
+        pair(tag("("), multispace_comment0),
+        key_parser(),
+        pair(multispace_comment0, tag(")")),
+    );
+    let keyed_by_parser = preceded(
+        tuple((tag("keyed"), space_comment1, tag("by"), space_comment1)),
+        key_parser(),
+    );
+
+    map(
+        preceded(space_comment0, alt((parenthesized_parser, keyed_by_parser))),
+        |(ident, ty)| Identity { ident, ty },
+    )(input)
+}
+
+/// State held by an aggregate, eg. `state { balance: Float }`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct State<'i> {
+    /// The `state` keyword, locating the block.
+    pub keyword: Span<'i>,
+    pub fields: Vec<Field<'i>>,
+}
+
+pub fn parse_state(input: Span) -> IResult<Span, State> {
+    let fields_parser = delimited_list0(char('{'), parse_field, char('}'));
+
+    map(
+        separated_pair(tag("state"), multispace_comment0, fields_parser),
+        |(keyword, fields)| State { keyword, fields },
     )(input)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Command<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
     pub ident: Span<'i>,
     pub params: Vec<Param<'i>>,
     pub return_type: ReturnType<'i>,
+    /// Errors the command can be rejected with, eg. `! (InsufficientFunds | AccountClosed)`.
+    pub errors: Vec<Span<'i>>,
 }
 
-pub fn parse_command(input: Span) -> IResult<Span, Command<'_>> {
+pub fn parse_command(input: Span) -> IResult<Span, Command> {
     let ident_parser = parse_snake_ident;
     let params_parser = parse_params;
-    let ident_params_parser = pair(
-        separated_pair(ident_parser, multispace0, params_parser),
+    let ident_params_parser = tuple((
+        parse_docs,
+        parse_annotations,
+        separated_pair(ident_parser, multispace_comment0, params_parser),
         preceded(
-            tuple((multispace0, tag("->"), multispace0)),
+            tuple((multispace_comment0, tag("->"), multispace_comment0)),
             parse_return_type,
         ),
-    );
-    map(ident_params_parser, |((ident, params), return_type)| {
-        Command {
+        opt(preceded(
+            tuple((multispace_comment0, char('!'), multispace_comment0)),
+            parse_command_errors,
+        )),
+    ));
+    map(
+        ident_params_parser,
+        |(docs, annotations, (ident, params), return_type, errors)| Command {
+            docs,
+            annotations,
             ident,
             params,
             return_type,
-        }
-    })(input)
+            errors: errors.unwrap_or_default(),
+        },
+    )(input)
+}
+
+/// Errors raised by a command, either a single error or alternatives, eg. `(Closed | Frozen)`.
+pub fn parse_command_errors(input: Span) -> IResult<Span, Vec<Span>> {
+    let single_error_parser = map(parse_camel_ident, |error| vec![error]);
+    let tuple_errors_parser = delimited(
+        pair(tag("("), multispace_comment0),
+        separated_list1(
+            tuple((multispace_comment0, tag("|"), multispace_comment0)),
+            parse_camel_ident,
+        ),
+        pair(multispace_comment0, tag(")")),
+    );
+
+    alt((single_error_parser, tuple_errors_parser))(input)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Param<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
     pub ident: Span<'i>,
     pub ty: Type<'i>,
+    pub constraints: Vec<Annotation<'i>>,
+    pub default: Option<Literal<'i>>,
 }
 
-pub fn parse_params(input: Span) -> IResult<Span, Vec<Param<'_>>> {
+pub fn parse_params(input: Span) -> IResult<Span, Vec<Param>> {
     delimited(
-        tuple((tag("("), multispace0)),
-        separated_list0(tuple((multispace0, tag(","), multispace0)), parse_param),
-        tuple((multispace0, tag(")"))),
+        tuple((tag("("), multispace_comment0)),
+        separated_list0(
+            tuple((multispace_comment0, tag(","), multispace_comment0)),
+            parse_param,
+        ),
+        tuple((multispace_comment0, tag(")"))),
     )(input)
 }
 
-pub fn parse_param(input: Span) -> IResult<Span, Param<'_>> {
+pub fn parse_param(input: Span) -> IResult<Span, Param> {
     map(
-        separated_pair(
+        tuple((
+            parse_docs,
+            parse_annotations,
             parse_snake_ident,
-            tuple((space0, tag(":"), space0)),
-            parse_type,
-        ),
-        |(ident, ty)| Param { ident, ty },
+            preceded(
+                tuple((space_comment0, tag(":"), space_comment0)),
+                parse_type,
+            ),
+            parse_constraints,
+            opt(parse_default),
+        )),
+        |(docs, annotations, ident, ty, constraints, default)| Param {
+            docs,
+            annotations,
+            ident,
+            ty,
+            constraints,
+            default,
+        },
     )(input)
 }
 
@@ -90,23 +232,33 @@ pub fn parse_param(input: Span) -> IResult<Span, Param<'_>> {
 pub enum ReturnType<'i> {
     Single(ReturnTypeOptionalOrRequired<'i>),
     Tuple(Vec<ReturnTypeOptionalOrRequired<'i>>),
+    /// `()`, for a command which never emits events.
+    None,
 }
 
-pub fn parse_return_type(input: Span) -> IResult<Span, ReturnType<'_>> {
+pub fn parse_return_type(input: Span) -> IResult<Span, ReturnType> {
     let single_return_type_parse = map(parse_return_type_optional_or_required, ReturnType::Single);
+    let none_return_type_parse = value(
+        ReturnType::None,
+        tuple((tag("("), multispace_comment0, tag(")"))),
+    );
     let tuple_return_type_parse = map(
         delimited(
-            pair(tag("("), multispace0),
-            separated_list0(
-                tuple((multispace0, tag("|"), multispace0)),
+            pair(tag("("), multispace_comment0),
+            separated_list1(
+                tuple((multispace_comment0, tag("|"), multispace_comment0)),
                 parse_return_type_optional_or_required,
             ),
-            pair(multispace0, tag(")")),
+            pair(multispace_comment0, tag(")")),
         ),
         ReturnType::Tuple,
     );
 
-    alt((single_return_type_parse, tuple_return_type_parse))(input)
+    alt((
+        single_return_type_parse,
+        none_return_type_parse,
+        tuple_return_type_parse,
+    ))(input)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -117,7 +269,7 @@ pub enum ReturnTypeOptionalOrRequired<'i> {
 
 pub fn parse_return_type_optional_or_required(
     input: Span,
-) -> IResult<Span, ReturnTypeOptionalOrRequired<'_>> {
+) -> IResult<Span, ReturnTypeOptionalOrRequired> {
     map(pair(parse_camel_ident, opt(char('?'))), |(ty, optional)| {
         if optional.is_some() {
             ReturnTypeOptionalOrRequired::Optional(ty)
@@ -0,0 +1,79 @@
+use nom::{
+    character::complete::char,
+    combinator::{cut, map, opt},
+    multi::{many0, separated_list0},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+};
+use nom_supreme::ParserExt;
+
+use super::{
+    ident::parse_snake_ident,
+    literal::{parse_literal, Literal},
+    parsers::{multispace_comment0, space_comment0},
+    IResult, Span,
+};
+
+/// Annotation attached to the item which follows it, eg. `@deprecated("use close_account")`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Annotation<'i> {
+    pub ident: Span<'i>,
+    pub args: Vec<Literal<'i>>,
+}
+
+/// Zero or more annotations attached to the item which follows them.
+pub fn parse_annotations(input: Span) -> IResult<Span, Vec<Annotation>> {
+    many0(terminated(parse_annotation, multispace_comment0))(input)
+}
+
+/// Constraints following the type of a field or param on the same line, eg. `@length(1, 100)`.
+pub fn parse_constraints(input: Span) -> IResult<Span, Vec<Annotation>> {
+    many0(preceded(space_comment0, parse_annotation))(input)
+}
+
+/// Annotation with optional arguments, eg. `@since("0.3.0")` or `@internal`.
+///
+/// Once the opening parenthesis is found, malformed arguments are reported where they occur.
+pub fn parse_annotation(input: Span) -> IResult<Span, Annotation> {
+    let separator = || tuple((multispace_comment0, char(','), multispace_comment0));
+    let args_parser = preceded(
+        char('('),
+        cut(delimited(
+            multispace_comment0,
+            separated_list0(separator(), parse_literal),
+            tuple((opt(separator()), multispace_comment0, char(')'))),
+        ))
+        .context("annotation arguments"),
+    );
+
+    map(
+        pair(preceded(char('@'), parse_snake_ident), opt(args_parser)),
+        |(ident, args)| Annotation {
+            ident,
+            args: args.unwrap_or_default(),
+        },
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_annotation, Literal, Span};
+
+    #[test]
+    fn it_parses_annotations() {
+        let (_, annotation) = parse_annotation(Span::new("@internal")).unwrap();
+        assert_eq!(*annotation.ident.fragment(), "internal");
+        assert!(annotation.args.is_empty());
+
+        let (_, annotation) =
+            parse_annotation(Span::new(r#"@rename( "initialBalance", 2 )"#)).unwrap();
+        assert_eq!(*annotation.ident.fragment(), "rename");
+        assert!(matches!(
+            annotation.args.as_slice(),
+            [Literal::String(name), Literal::Int(version)]
+                if *name.fragment() == r#""initialBalance""# && *version.fragment() == "2"
+        ));
+
+        let err = parse_annotation(Span::new("@since(0.3.0)")).unwrap_err();
+        assert!(matches!(err, nom::Err::Failure(_)));
+    }
+}
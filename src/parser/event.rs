@@ -1,43 +1,103 @@
+use std::path::Path;
+
 use nom::{
-    character::streaming::space0,
-    combinator::map,
-    sequence::{separated_pair, tuple},
+    combinator::{map, opt},
+    sequence::{preceded, tuple},
 };
 use nom_supreme::tag::complete::tag;
 
 use super::{
+    annotation::{parse_annotations, parse_constraints, Annotation},
     ident::parse_snake_ident,
-    parsers::keyword_ident_structure,
+    literal::{parse_default, Literal},
+    parsers::{keyword_ident_structure, parse_docs, space_comment0},
     types::{parse_type, Type},
     IResult, Span,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Event<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
+    pub ident: Span<'i>,
+    pub fields: Vec<Field<'i>>,
+    /// Package of the file declaring it, set once the whole file is parsed.
+    pub package: Option<Span<'i>>,
+    /// File the declaration was imported from, set when it is merged into an importing schema.
+    pub file: Option<&'i Path>,
+}
+
+pub fn parse_event(input: Span) -> IResult<Span, Event> {
+    map(
+        keyword_ident_structure("event"),
+        |(docs, annotations, ident, fields)| Event {
+            docs,
+            annotations,
+            ident,
+            fields,
+            package: None,
+            file: None,
+        },
+    )(input)
+}
+
+/// Domain error a command can be rejected with, eg. `error InsufficientFunds { balance: Float }`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandError<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
     pub ident: Span<'i>,
     pub fields: Vec<Field<'i>>,
+    /// Package of the file declaring it, set once the whole file is parsed.
+    pub package: Option<Span<'i>>,
+    /// File the declaration was imported from, set when it is merged into an importing schema.
+    pub file: Option<&'i Path>,
 }
 
-pub fn parse_event(input: Span) -> IResult<Span, Event<'_>> {
-    map(keyword_ident_structure("event"), |(ident, fields)| Event {
-        ident,
-        fields,
-    })(input)
+pub fn parse_command_error(input: Span) -> IResult<Span, CommandError> {
+    map(
+        keyword_ident_structure("error"),
+        |(docs, annotations, ident, fields)| CommandError {
+            docs,
+            annotations,
+            ident,
+            fields,
+            package: None,
+            file: None,
+        },
+    )(input)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Field<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
     pub ident: Span<'i>,
     pub ty: Type<'i>,
+    pub constraints: Vec<Annotation<'i>>,
+    pub default: Option<Literal<'i>>,
 }
 
-pub fn parse_field(input: Span) -> IResult<Span, Field<'_>> {
+pub fn parse_field(input: Span) -> IResult<Span, Field> {
     map(
-        separated_pair(
+        tuple((
+            parse_docs,
+            parse_annotations,
             parse_snake_ident,
-            tuple((space0, tag(":"), space0)),
-            parse_type,
-        ),
-        |(ident, ty)| Field { ident, ty },
+            preceded(
+                tuple((space_comment0, tag(":"), space_comment0)),
+                parse_type,
+            ),
+            parse_constraints,
+            opt(parse_default),
+        )),
+        |(docs, annotations, ident, ty, constraints, default)| Field {
+            docs,
+            annotations,
+            ident,
+            ty,
+            constraints,
+            default,
+        },
     )(input)
 }
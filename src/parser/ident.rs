@@ -1,9 +1,9 @@
 use nom::{
     branch::alt,
-    character::complete::{alphanumeric1, satisfy},
+    character::complete::{alphanumeric1, char, satisfy},
     combinator::recognize,
     multi::many0,
-    sequence::pair,
+    sequence::{pair, preceded, terminated},
 };
 use nom_supreme::tag::complete::tag;
 
@@ -16,6 +16,22 @@ pub fn parse_camel_ident(input: Span) -> IResult<Span, Span> {
     ))(input)
 }
 
+/// Camel case identifier, optionally qualified by a package, eg. `common.Money`.
+pub fn parse_qualified_camel_ident(input: Span) -> IResult<Span, Span> {
+    recognize(pair(
+        many0(terminated(parse_snake_ident, char('.'))),
+        parse_camel_ident,
+    ))(input)
+}
+
+/// Dot separated snake case identifiers, eg. `banking.accounts`.
+pub fn parse_package_name(input: Span) -> IResult<Span, Span> {
+    recognize(pair(
+        parse_snake_ident,
+        many0(preceded(char('.'), parse_snake_ident)),
+    ))(input)
+}
+
 pub fn parse_snake_ident(input: Span) -> IResult<Span, Span> {
     recognize(pair(
         alt((
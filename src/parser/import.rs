@@ -0,0 +1,65 @@
+use nom::{
+    character::complete::char,
+    combinator::{map, opt},
+    sequence::{pair, preceded, terminated, tuple},
+};
+use nom_supreme::tag::complete::tag;
+
+use super::{
+    ident::parse_camel_ident,
+    literal::parse_string_literal,
+    parsers::{delimited_list0, space_comment0},
+    IResult, Span,
+};
+
+/// Import of another schema file, either whole or only the named declarations.
+/// - `import "shared/types.esdl"`
+/// - `import { Money, User } from "shared/types.esdl"`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Import<'i> {
+    pub names: Option<Vec<Span<'i>>>,
+    /// String literal of the imported path, including its quotes.
+    pub path: Span<'i>,
+}
+
+pub fn parse_import(input: Span) -> IResult<Span, Import> {
+    let names_parser = terminated(
+        delimited_list0(char('{'), parse_camel_ident, char('}')),
+        tuple((space_comment0, tag("from"), space_comment0)),
+    );
+
+    map(
+        preceded(
+            pair(tag("import"), space_comment0),
+            pair(opt(names_parser), parse_string_literal),
+        ),
+        |(names, path)| Import { names, path },
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_import, Span};
+
+    #[test]
+    fn it_parses_imports() {
+        let (_, import) = parse_import(Span::new(r#"import "shared/types.esdl""#)).unwrap();
+        assert_eq!(*import.path.fragment(), r#""shared/types.esdl""#);
+        assert!(import.names.is_none());
+
+        let (_, import) = parse_import(Span::new(
+            "import {\n    Money,\n    User\n} from \"shared/types.esdl\"",
+        ))
+        .unwrap();
+        let names: Vec<_> = import
+            .names
+            .unwrap()
+            .iter()
+            .map(|name| *name.fragment())
+            .collect();
+        assert_eq!(names, vec!["Money", "User"]);
+
+        assert!(parse_import(Span::new(r#"import { money } from "types.esdl""#)).is_err());
+        assert!(parse_import(Span::new("import { Money } from")).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use nom::{
+    branch::alt,
+    bytes::complete::is_not,
+    character::complete::{char, digit1, one_of},
+    combinator::{consumed, map, opt, recognize},
+    multi::{many0, separated_list0},
+    sequence::{delimited, pair, preceded, tuple},
+};
+use nom_supreme::tag::complete::tag;
+
+use super::{
+    ident::parse_camel_ident,
+    parsers::{multispace_comment0, space_comment0},
+    IResult, Span,
+};
+
+/// Literal value, used as the default of a field or param.
+///
+/// Each variant holds the span of the whole literal, including quotes and brackets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Literal<'i> {
+    String(Span<'i>),
+    Int(Span<'i>),
+    Float(Span<'i>),
+    Bool(Span<'i>),
+    Array(Span<'i>, Vec<Literal<'i>>),
+    EnumVariant(Span<'i>),
+}
+
+impl<'i> Literal<'i> {
+    pub fn span(&self) -> Span<'i> {
+        match self {
+            Literal::String(span)
+            | Literal::Int(span)
+            | Literal::Float(span)
+            | Literal::Bool(span)
+            | Literal::Array(span, _)
+            | Literal::EnumVariant(span) => *span,
+        }
+    }
+}
+
+/// Default value following a field or param type, eg. ` = "AUD"`.
+pub fn parse_default(input: Span) -> IResult<Span, Literal> {
+    preceded(
+        tuple((space_comment0, char('='), space_comment0)),
+        parse_literal,
+    )(input)
+}
+
+pub fn parse_literal(input: Span) -> IResult<Span, Literal> {
+    alt((
+        map(parse_string_literal, Literal::String),
+        map(parse_float_literal, Literal::Float),
+        map(parse_int_literal, Literal::Int),
+        map(parse_bool_literal, Literal::Bool),
+        parse_array_literal,
+        map(parse_camel_ident, Literal::EnumVariant),
+    ))(input)
+}
+
+/// String literal, eg. `"AUD"`, supporting the escapes `\"`, `\\`, `\n`, `\r` and `\t`.
+pub fn parse_string_literal(input: Span) -> IResult<Span, Span> {
+    recognize(delimited(
+        char('"'),
+        many0(alt((
+            is_not("\"\\"),
+            recognize(pair(char('\\'), one_of("\"\\nrt"))),
+        ))),
+        char('"'),
+    ))(input)
+}
+
+pub fn parse_float_literal(input: Span) -> IResult<Span, Span> {
+    recognize(tuple((opt(char('-')), digit1, char('.'), digit1)))(input)
+}
+
+pub fn parse_int_literal(input: Span) -> IResult<Span, Span> {
+    recognize(pair(opt(char('-')), digit1))(input)
+}
+
+pub fn parse_bool_literal(input: Span) -> IResult<Span, Span> {
+    alt((tag("true"), tag("false")))(input)
+}
+
+/// Array literal, eg. `[1, 2, 3]`, allowing a trailing comma.
+pub fn parse_array_literal(input: Span) -> IResult<Span, Literal> {
+    let separator = || tuple((multispace_comment0, char(','), multispace_comment0));
+
+    map(
+        consumed(delimited(
+            pair(char('['), multispace_comment0),
+            separated_list0(separator(), parse_literal),
+            tuple((opt(separator()), multispace_comment0, char(']'))),
+        )),
+        |(span, items)| Literal::Array(span, items),
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_literal, Literal, Span};
+
+    #[test]
+    fn it_parses_literals() {
+        let (_, literal) = parse_literal(Span::new(r#""say \"hi\"""#)).unwrap();
+        assert!(matches!(literal, Literal::String(s) if *s.fragment() == r#""say \"hi\"""#));
+
+        let (_, literal) = parse_literal(Span::new("-12.50")).unwrap();
+        assert!(matches!(literal, Literal::Float(s) if *s.fragment() == "-12.50"));
+
+        let (tail, literal) = parse_literal(Span::new("3.")).unwrap();
+        assert!(matches!(literal, Literal::Int(s) if *s.fragment() == "3"));
+        assert_eq!(*tail.fragment(), ".");
+
+        let (_, literal) = parse_literal(Span::new("[true, [Aud] ,]")).unwrap();
+        match literal {
+            Literal::Array(span, items) => {
+                assert_eq!(*span.fragment(), "[true, [Aud] ,]");
+                assert!(matches!(items[0], Literal::Bool(s) if *s.fragment() == "true"));
+                assert!(matches!(
+                    &items[1],
+                    Literal::Array(_, items) if matches!(items[0], Literal::EnumVariant(s) if *s.fragment() == "Aud")
+                ));
+            }
+            literal => panic!("expected array, found {literal:?}"),
+        }
+
+        assert!(parse_literal(Span::new(r#""unterminated"#)).is_err());
+    }
+}
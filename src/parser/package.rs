@@ -0,0 +1,31 @@
+use nom::sequence::{pair, preceded};
+use nom_supreme::tag::complete::tag;
+
+use super::{ident::parse_package_name, parsers::space_comment1, IResult, Span};
+
+/// Package declaration, eg. `package banking.accounts`, returning the package name.
+pub fn parse_package(input: Span) -> IResult<Span, Span> {
+    preceded(pair(tag("package"), space_comment1), parse_package_name)(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_package, Span};
+
+    #[test]
+    fn it_parses_packages() {
+        let (tail, package) = parse_package(Span::new("package banking.accounts\n")).unwrap();
+        assert_eq!(*package.fragment(), "banking.accounts");
+        assert_eq!(*tail.fragment(), "\n");
+
+        let (tail, package) = parse_package(Span::new("package common.")).unwrap();
+        assert_eq!(*package.fragment(), "common");
+        assert_eq!(*tail.fragment(), ".");
+
+        let (_, package) = parse_package(Span::new("package /* c */ common")).unwrap();
+        assert_eq!(*package.fragment(), "common");
+
+        assert!(parse_package(Span::new("package Banking")).is_err());
+        assert!(parse_package(Span::new("packagebanking")).is_err());
+    }
+}
@@ -1,59 +1,120 @@
-use std::ops::RangeFrom;
-
 use nom::{
     branch::alt,
-    character::complete::{char, multispace0, newline, space0, space1},
-    combinator::map,
-    error::ParseError,
-    multi::separated_list0,
-    sequence::{delimited, pair, preceded, separated_pair, tuple},
-    AsChar, IResult, InputIter, InputLength, InputTakeAtPosition, Parser, Slice,
+    bytes::complete::{is_not, take_until},
+    character::complete::{char, line_ending, multispace1, space1},
+    combinator::{map, not, opt, recognize},
+    multi::{many0, many1, separated_list0},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
+    Parser,
 };
 use nom_supreme::tag::complete::tag;
 
 use super::{
+    annotation::{parse_annotations, Annotation},
     event::{parse_field, Field},
     ident::parse_camel_ident,
-    Span,
+    Error, IResult, Span,
 };
 
-pub fn multinewline1<T, E: ParseError<T>>(input: T) -> IResult<T, (T, char, T), E>
-where
-    T: InputTakeAtPosition + InputIter + Slice<RangeFrom<usize>> + Clone,
-    <T as InputTakeAtPosition>::Item: AsChar + Clone,
-    <T as InputIter>::Item: AsChar + Clone,
-{
-    tuple((space0, newline, multispace0))(input)
+/// Line comment, eg. `// comment`.
+///
+/// The trailing newline is not consumed.
+pub fn line_comment(input: Span) -> IResult<Span, Span> {
+    recognize(tuple((not(doc_comment), tag("//"), opt(is_not("\r\n")))))(input)
+}
+
+/// Doc comment, eg. `/// Documentation`.
+///
+/// Outputs the text following the `///`, without the trailing newline.
+pub fn doc_comment(input: Span) -> IResult<Span, Span> {
+    preceded(
+        pair(tag("///"), not(char('/'))),
+        recognize(opt(is_not("\r\n"))),
+    )(input)
+}
+
+/// Zero or more doc comments attached to the item which follows them.
+pub fn parse_docs(input: Span) -> IResult<Span, Vec<Span>> {
+    many0(terminated(doc_comment, multispace_comment0))(input)
+}
+
+/// Block comment, eg. `/* comment */`.
+pub fn block_comment(input: Span) -> IResult<Span, Span> {
+    recognize(tuple((tag("/*"), take_until("*/"), tag("*/"))))(input)
+}
+
+/// Zero or more spaces, tabs or block comments, without crossing a line.
+pub fn space_comment0(input: Span) -> IResult<Span, Span> {
+    recognize(many0(alt((space1, block_comment))))(input)
 }
 
-pub fn delimited_multiline_list0<I, O1, O2, O3, E: ParseError<I>, F, G, H>(
-    first: impl Fn() -> F,
+/// One or more spaces, tabs or block comments, without crossing a line.
+pub fn space_comment1(input: Span) -> IResult<Span, Span> {
+    recognize(many1(alt((space1, block_comment))))(input)
+}
+
+/// Zero or more whitespace characters or comments.
+pub fn multispace_comment0(input: Span) -> IResult<Span, Span> {
+    recognize(many0(alt((multispace1, line_comment, block_comment))))(input)
+}
+
+/// One or more whitespace characters or comments.
+pub fn multispace_comment1(input: Span) -> IResult<Span, Span> {
+    recognize(many1(alt((multispace1, line_comment, block_comment))))(input)
+}
+
+/// At least one line break, surrounded by any whitespace or comments.
+///
+/// A line comment may trail the content before the line break.
+pub fn multinewline1(input: Span) -> IResult<Span, Span> {
+    recognize(tuple((
+        space_comment0,
+        opt(line_comment),
+        line_ending,
+        multispace_comment0,
+    )))(input)
+}
+
+/// List separated by commas or line breaks, allowing a trailing comma.
+pub fn delimited_list0<'i, O1, O2, O3, F, G, H>(
+    first: F,
     second: G,
-    third: impl Fn() -> H,
-) -> impl FnMut(I) -> IResult<I, Vec<O2>, E>
+    third: H,
+) -> impl FnMut(Span<'i>) -> IResult<Span<'i>, Vec<O2>>
 where
-    I: InputTakeAtPosition + InputIter + InputLength + Slice<RangeFrom<usize>> + Clone,
-    <I as InputTakeAtPosition>::Item: AsChar + Clone,
-    <I as InputIter>::Item: AsChar + Clone,
-    F: Parser<I, O1, E>,
-    G: Parser<I, O2, E>,
-    H: Parser<I, O3, E>,
+    F: Parser<Span<'i>, O1, Error<Span<'i>>>,
+    G: Parser<Span<'i>, O2, Error<Span<'i>>>,
+    H: Parser<Span<'i>, O3, Error<Span<'i>>>,
 {
-    alt((
-        map(tuple((first(), multispace0, third())), |_| Vec::new()),
-        delimited(
-            pair(first(), multinewline1),
-            separated_list0(multinewline1, second),
-            pair(multinewline1, third()),
-        ),
-    ))
+    let separator = || {
+        alt((
+            recognize(tuple((space_comment0, char(','), multispace_comment0))),
+            multinewline1,
+        ))
+    };
+
+    delimited(
+        pair(first, multispace_comment0),
+        separated_list0(separator(), second),
+        tuple((opt(separator()), multispace_comment0, third)),
+    )
 }
 
+/// Docs, annotations, identifier and fields of a structure, eg. `event Foo { .. }`.
+pub type KeywordIdentStructure<'i> = (Vec<Span<'i>>, Vec<Annotation<'i>>, Span<'i>, Vec<Field<'i>>);
+
 pub fn keyword_ident_structure<'i>(
     keyword: &'static str,
-) -> impl FnMut(Span<'i>) -> super::IResult<Span<'i>, (Span<'i>, Vec<Field<'i>>)> {
-    let ident_parser = preceded(pair(tag(keyword), space1), parse_camel_ident);
-    let fields_parser = delimited_multiline_list0(|| char('{'), parse_field, || char('}'));
+) -> impl FnMut(Span<'i>) -> IResult<Span<'i>, KeywordIdentStructure<'i>> {
+    let ident_parser = preceded(pair(tag(keyword), space_comment1), parse_camel_ident);
+    let fields_parser = delimited_list0(char('{'), parse_field, char('}'));
 
-    separated_pair(ident_parser, multispace0, fields_parser)
+    map(
+        tuple((
+            parse_docs,
+            parse_annotations,
+            separated_pair(ident_parser, multispace_comment0, fields_parser),
+        )),
+        |(docs, annotations, (ident, fields))| (docs, annotations, ident, fields),
+    )
 }
@@ -0,0 +1,529 @@
+//! Parser generated from the pest grammar in `schema.pest`.
+//!
+//! Produces the same [`Schema`] as the nom parser, so tooling built on the
+//! grammar sees schemas exactly as [`crate::parse`] does.
+
+use std::str::FromStr;
+
+use ::pest::{
+    error::{Error as PestError, ErrorVariant},
+    iterators::Pair,
+    Parser,
+};
+use nom::Slice;
+use pest_derive::Parser;
+use semver::Version;
+
+use super::{
+    aggregate::{
+        Aggregate, Command, Identity, Param, ReturnType, ReturnTypeOptionalOrRequired, State,
+    },
+    annotation::Annotation,
+    event::{CommandError, Event, Field},
+    import::Import,
+    literal::Literal,
+    projection::Projection,
+    saga::{Saga, SagaStep, SagaTarget},
+    schema::Schema,
+    types::{
+        Alias, CustomScalar, CustomType, Enum, EnumVariant, OptionalOrRequiredType, Scalar,
+        ScalarOrUserType, Type, Union, UnionVariant,
+    },
+    Span,
+};
+
+/// Parser for the ESDL grammar defined in `schema.pest`.
+#[derive(Parser)]
+#[grammar = "../schema.pest"]
+pub struct SchemaParser;
+
+pub fn parse(input: &str) -> Result<Schema<'_>, PestError<Rule>> {
+    let root = SchemaParser::parse(Rule::schema, input)?.next().unwrap();
+    let builder = Builder {
+        input: Span::new(input),
+    };
+
+    let mut schema = Schema::default();
+    for item in root.into_inner() {
+        match item.as_rule() {
+            Rule::aggregate => schema.aggregates.push(builder.aggregate(item)),
+            Rule::projection => schema.projections.push(builder.projection(item)),
+            Rule::saga => schema.sagas.push(builder.saga(item)),
+            Rule::version => schema.versions.push(builder.version(item)?),
+            Rule::package => schema
+                .packages
+                .push(builder.span(&item.into_inner().next().unwrap())),
+            Rule::import => schema.imports.push(builder.import(item)),
+            Rule::event => schema.events.push(builder.event(item)),
+            Rule::error_type => schema.errors.push(builder.error_type(item)),
+            Rule::custom_type => schema.types.push(builder.custom_type(item)),
+            Rule::union => schema.unions.push(builder.union(item)),
+            Rule::alias => schema.aliases.push(builder.alias(item)),
+            Rule::enum_type => schema.enums.push(builder.enum_type(item)),
+            Rule::custom_scalar => schema.scalars.push(builder.custom_scalar(item)),
+            Rule::EOI => {}
+            rule => unreachable!("unexpected rule {rule:?}"),
+        }
+    }
+
+    schema.set_package();
+
+    Ok(schema)
+}
+
+/// Converts pest pairs into the parsed schema items, with spans located in the
+/// original input.
+struct Builder<'i> {
+    input: Span<'i>,
+}
+
+impl<'i> Builder<'i> {
+    fn span(&self, pair: &Pair<'i, Rule>) -> Span<'i> {
+        let span = pair.as_span();
+        self.input.slice(span.start()..span.end())
+    }
+
+    fn docs(&self, pair: Pair<'i, Rule>) -> Vec<Span<'i>> {
+        pair.into_inner().map(|doc| self.span(&doc)).collect()
+    }
+
+    fn annotations(&self, pair: Pair<'i, Rule>) -> Vec<Annotation<'i>> {
+        pair.into_inner()
+            .map(|annotation| {
+                let mut inner = annotation.into_inner();
+                Annotation {
+                    ident: self.span(&inner.next().unwrap()),
+                    args: inner.map(|literal| self.literal(literal)).collect(),
+                }
+            })
+            .collect()
+    }
+
+    fn version(&self, pair: Pair<'i, Rule>) -> Result<Version, PestError<Rule>> {
+        let number = pair.into_inner().next().unwrap();
+        Version::from_str(number.as_str()).map_err(|err| {
+            PestError::new_from_span(
+                ErrorVariant::CustomError {
+                    message: err.to_string(),
+                },
+                number.as_span(),
+            )
+        })
+    }
+
+    fn import(&self, pair: Pair<'i, Rule>) -> Import<'i> {
+        let mut inner = pair.into_inner().peekable();
+        let names = inner
+            .next_if(|pair| pair.as_rule() == Rule::import_names)
+            .map(|names| names.into_inner().map(|name| self.span(&name)).collect());
+        Import {
+            names,
+            path: self.span(&inner.next().unwrap()),
+        }
+    }
+
+    fn aggregate(&self, pair: Pair<'i, Rule>) -> Aggregate<'i> {
+        let mut inner = pair.into_inner();
+        let docs = self.docs(inner.next().unwrap());
+        let annotations = self.annotations(inner.next().unwrap());
+        let ident = self.span(&inner.next().unwrap());
+        let mut inner = inner.peekable();
+        let identity = inner
+            .next_if(|pair| pair.as_rule() == Rule::identity)
+            .map(|identity| {
+                let mut inner = identity.into_inner();
+                Identity {
+                    ident: self.span(&inner.next().unwrap()),
+                    ty: self.ty(inner.next().unwrap()),
+                }
+            });
+        let (states, commands): (Vec<_>, Vec<_>) =
+            inner.partition(|item| item.as_rule() == Rule::state);
+
+        Aggregate {
+            docs,
+            annotations,
+            ident,
+            identity,
+            states: states.into_iter().map(|state| self.state(state)).collect(),
+            commands: commands
+                .into_iter()
+                .map(|command| self.command(command))
+                .collect(),
+        }
+    }
+
+    fn projection(&self, pair: Pair<'i, Rule>) -> Projection<'i> {
+        let mut inner = pair.into_inner();
+        let docs = self.docs(inner.next().unwrap());
+        let annotations = self.annotations(inner.next().unwrap());
+        let ident = self.span(&inner.next().unwrap());
+        let (subscriptions, fields): (Vec<_>, Vec<_>) =
+            inner.partition(|pair| pair.as_rule() == Rule::subscriptions);
+
+        Projection {
+            docs,
+            annotations,
+            ident,
+            fields: fields.into_iter().map(|field| self.field(field)).collect(),
+            events: subscriptions
+                .into_iter()
+                .flat_map(|events| events.into_inner())
+                .map(|event| self.span(&event))
+                .collect(),
+        }
+    }
+
+    fn saga(&self, pair: Pair<'i, Rule>) -> Saga<'i> {
+        let mut inner = pair.into_inner();
+        Saga {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            steps: inner.map(|step| self.saga_step(step)).collect(),
+        }
+    }
+
+    fn saga_step(&self, pair: Pair<'i, Rule>) -> SagaStep<'i> {
+        let mut inner = pair.into_inner();
+        let event = self.span(&inner.next().unwrap());
+        let target = inner.next().unwrap();
+        let target = match target.as_rule() {
+            Rule::done => SagaTarget::Done,
+            Rule::command_ref => {
+                let mut inner = target.into_inner();
+                SagaTarget::Command {
+                    aggregate: self.span(&inner.next().unwrap()),
+                    command: self.span(&inner.next().unwrap()),
+                }
+            }
+            rule => unreachable!("unexpected rule {rule:?}"),
+        };
+
+        SagaStep { event, target }
+    }
+
+    fn state(&self, pair: Pair<'i, Rule>) -> State<'i> {
+        let start = pair.as_span().start();
+        State {
+            keyword: self.input.slice(start..start + "state".len()),
+            fields: pair.into_inner().map(|field| self.field(field)).collect(),
+        }
+    }
+
+    fn command(&self, pair: Pair<'i, Rule>) -> Command<'i> {
+        let mut inner = pair.into_inner();
+        let docs = self.docs(inner.next().unwrap());
+        let annotations = self.annotations(inner.next().unwrap());
+        let ident = self.span(&inner.next().unwrap());
+        let params = inner
+            .next()
+            .unwrap()
+            .into_inner()
+            .map(|param| self.param(param))
+            .collect();
+        let return_type = inner.next().unwrap();
+        let return_type = match return_type.as_rule() {
+            Rule::event_ref => ReturnType::Single(self.event_ref(return_type)),
+            Rule::return_none => ReturnType::None,
+            Rule::return_tuple => ReturnType::Tuple(
+                return_type
+                    .into_inner()
+                    .map(|event| self.event_ref(event))
+                    .collect(),
+            ),
+            rule => unreachable!("unexpected rule {rule:?}"),
+        };
+        let errors = inner
+            .next()
+            .map(|errors| errors.into_inner().map(|error| self.span(&error)).collect())
+            .unwrap_or_default();
+
+        Command {
+            docs,
+            annotations,
+            ident,
+            params,
+            return_type,
+            errors,
+        }
+    }
+
+    fn param(&self, pair: Pair<'i, Rule>) -> Param<'i> {
+        let mut inner = pair.into_inner();
+        Param {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            ty: self.ty(inner.next().unwrap()),
+            constraints: self.annotations(inner.next().unwrap()),
+            default: inner.next().map(|literal| self.literal(literal)),
+        }
+    }
+
+    fn event_ref(&self, pair: Pair<'i, Rule>) -> ReturnTypeOptionalOrRequired<'i> {
+        let mut inner = pair.into_inner();
+        let ident = self.span(&inner.next().unwrap());
+        if inner.next().is_some() {
+            ReturnTypeOptionalOrRequired::Optional(ident)
+        } else {
+            ReturnTypeOptionalOrRequired::Required(ident)
+        }
+    }
+
+    fn event(&self, pair: Pair<'i, Rule>) -> Event<'i> {
+        let mut inner = pair.into_inner();
+        Event {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            fields: inner.map(|field| self.field(field)).collect(),
+            package: None,
+            file: None,
+        }
+    }
+
+    fn error_type(&self, pair: Pair<'i, Rule>) -> CommandError<'i> {
+        let mut inner = pair.into_inner();
+        CommandError {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            fields: inner.map(|field| self.field(field)).collect(),
+            package: None,
+            file: None,
+        }
+    }
+
+    fn custom_type(&self, pair: Pair<'i, Rule>) -> CustomType<'i> {
+        let mut inner = pair.into_inner().peekable();
+        CustomType {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            params: inner
+                .next_if(|pair| pair.as_rule() == Rule::type_params)
+                .map(|params| params.into_inner().map(|param| self.span(&param)).collect())
+                .unwrap_or_default(),
+            fields: inner.map(|field| self.field(field)).collect(),
+            package: None,
+            file: None,
+        }
+    }
+
+    fn union(&self, pair: Pair<'i, Rule>) -> Union<'i> {
+        let mut inner = pair.into_inner();
+        Union {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            variants: inner.map(|variant| self.union_variant(variant)).collect(),
+            package: None,
+            file: None,
+        }
+    }
+
+    fn union_variant(&self, pair: Pair<'i, Rule>) -> UnionVariant<'i> {
+        let mut inner = pair.into_inner();
+        UnionVariant {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            fields: inner.map(|field| self.field(field)).collect(),
+        }
+    }
+
+    fn alias(&self, pair: Pair<'i, Rule>) -> Alias<'i> {
+        let mut inner = pair.into_inner();
+        Alias {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            ty: self.ty(inner.next().unwrap()),
+            package: None,
+            file: None,
+        }
+    }
+
+    fn enum_type(&self, pair: Pair<'i, Rule>) -> Enum<'i> {
+        let mut inner = pair.into_inner();
+        Enum {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            variants: inner
+                .map(|variant| {
+                    let mut inner = variant.into_inner();
+                    EnumVariant {
+                        docs: self.docs(inner.next().unwrap()),
+                        annotations: self.annotations(inner.next().unwrap()),
+                        ident: self.span(&inner.next().unwrap()),
+                    }
+                })
+                .collect(),
+            package: None,
+            file: None,
+        }
+    }
+
+    fn custom_scalar(&self, pair: Pair<'i, Rule>) -> CustomScalar<'i> {
+        let mut inner = pair.into_inner();
+        CustomScalar {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            base: inner.next().map(|base| self.scalar(base)),
+            package: None,
+            file: None,
+        }
+    }
+
+    fn field(&self, pair: Pair<'i, Rule>) -> Field<'i> {
+        let mut inner = pair.into_inner();
+        Field {
+            docs: self.docs(inner.next().unwrap()),
+            annotations: self.annotations(inner.next().unwrap()),
+            ident: self.span(&inner.next().unwrap()),
+            ty: self.ty(inner.next().unwrap()),
+            constraints: self.annotations(inner.next().unwrap()),
+            default: inner.next().map(|literal| self.literal(literal)),
+        }
+    }
+
+    fn literal(&self, pair: Pair<'i, Rule>) -> Literal<'i> {
+        let span = self.span(&pair);
+        match pair.as_rule() {
+            Rule::string_literal => Literal::String(span),
+            Rule::float_literal => Literal::Float(span),
+            Rule::int_literal => Literal::Int(span),
+            Rule::bool_literal => Literal::Bool(span),
+            Rule::array_literal => Literal::Array(
+                span,
+                pair.into_inner()
+                    .map(|literal| self.literal(literal))
+                    .collect(),
+            ),
+            Rule::camel_ident => Literal::EnumVariant(span),
+            rule => unreachable!("unexpected rule {rule:?}"),
+        }
+    }
+
+    fn ty(&self, pair: Pair<'i, Rule>) -> Type<'i> {
+        match pair.as_rule() {
+            Rule::single_ty => Type::Single(self.optional_or_required(pair)),
+            Rule::array_ty => {
+                let mut inner = pair.into_inner();
+                Type::Array {
+                    inner: Box::new(self.ty(inner.next().unwrap())),
+                    optional: inner.next().is_some(),
+                }
+            }
+            Rule::map_ty => {
+                let mut inner = pair.into_inner();
+                Type::Map {
+                    key: self.scalar_or_user(inner.next().unwrap()),
+                    value: Box::new(self.ty(inner.next().unwrap())),
+                    optional: inner.next().is_some(),
+                }
+            }
+            rule => unreachable!("unexpected rule {rule:?}"),
+        }
+    }
+
+    fn optional_or_required(&self, pair: Pair<'i, Rule>) -> OptionalOrRequiredType<'i> {
+        let mut inner = pair.into_inner();
+        let ty = self.scalar_or_user(inner.next().unwrap());
+        if inner.next().is_some() {
+            OptionalOrRequiredType::Optional(ty)
+        } else {
+            OptionalOrRequiredType::Required(ty)
+        }
+    }
+
+    fn scalar_or_user(&self, pair: Pair<'i, Rule>) -> ScalarOrUserType<'i> {
+        match pair.as_rule() {
+            Rule::scalar => ScalarOrUserType::Scalar(self.scalar(pair)),
+            Rule::user_ident => ScalarOrUserType::UserDefined(self.span(&pair)),
+            Rule::generic_ty => {
+                let mut inner = pair.into_inner();
+                ScalarOrUserType::Generic {
+                    ident: self.span(&inner.next().unwrap()),
+                    args: inner.map(|arg| self.ty(arg)).collect(),
+                }
+            }
+            rule => unreachable!("unexpected rule {rule:?}"),
+        }
+    }
+
+    fn scalar(&self, pair: Pair<'i, Rule>) -> Scalar {
+        match pair.as_str() {
+            "String" => Scalar::String,
+            "Int" => Scalar::Int,
+            "UInt" => Scalar::UInt,
+            "Long" => Scalar::Long,
+            "ULong" => Scalar::ULong,
+            "Float" => Scalar::Float,
+            "Double" => Scalar::Double,
+            "Decimal" => Scalar::Decimal,
+            "Bool" => Scalar::Bool,
+            "Bytes" => Scalar::Bytes,
+            "Timestamp" => Scalar::Timestamp,
+            "Date" => Scalar::Date,
+            "Duration" => Scalar::Duration,
+            "Uuid" => Scalar::Uuid,
+            scalar => unreachable!("unexpected scalar {scalar}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    /// Runs both parsers over every schema in `dir`, failing on any
+    /// disagreement in their output or in whether they accept the schema.
+    fn check_corpus(dir: &str, valid: bool) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+        let mut paths: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "esdl"))
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty(), "no schemas found in {}", dir.display());
+
+        for path in paths {
+            let input = fs::read_to_string(&path).unwrap();
+            let nom = crate::parser::parse(input.as_str());
+            let pest = super::parse(&input);
+
+            assert_eq!(
+                nom.is_ok(),
+                valid,
+                "nom parser result for {}: {nom:?}",
+                path.display()
+            );
+            assert_eq!(
+                nom.ok(),
+                pest.ok(),
+                "parsers disagree on {}",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn it_agrees_with_nom_on_examples() {
+        check_corpus("examples", true);
+    }
+
+    #[test]
+    fn it_agrees_with_nom_on_valid_schemas() {
+        check_corpus("tests/corpus/valid", true);
+    }
+
+    #[test]
+    fn it_agrees_with_nom_on_invalid_schemas() {
+        check_corpus("tests/corpus/invalid", false);
+    }
+}
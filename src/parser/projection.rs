@@ -0,0 +1,75 @@
+use nom::{
+    character::complete::char,
+    combinator::map,
+    sequence::{pair, preceded, tuple},
+};
+use nom_supreme::tag::complete::tag;
+
+use super::{
+    annotation::Annotation,
+    event::Field,
+    ident::parse_camel_ident,
+    parsers::{delimited_list0, keyword_ident_structure, multispace_comment0},
+    IResult, Span,
+};
+
+/// Read model built from the events it subscribes to.
+///
+/// ```text
+/// projection AccountSummary {
+///   balance: Float
+/// } on (OpenedAccount, DepositedFunds)
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Projection<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
+    pub ident: Span<'i>,
+    pub fields: Vec<Field<'i>>,
+    pub events: Vec<Span<'i>>,
+}
+
+pub fn parse_projection(input: Span) -> IResult<Span, Projection> {
+    let events_parser = preceded(
+        tuple((multispace_comment0, tag("on"), multispace_comment0)),
+        delimited_list0(char('('), parse_camel_ident, char(')')),
+    );
+
+    map(
+        pair(keyword_ident_structure("projection"), events_parser),
+        |((docs, annotations, ident, fields), events)| Projection {
+            docs,
+            annotations,
+            ident,
+            fields,
+            events,
+        },
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_projection, Span};
+
+    #[test]
+    fn it_parses_projections() {
+        let (tail, projection) = parse_projection(Span::new(
+            "projection AccountSummary {\n    balance: Float\n} on (OpenedAccount, DepositedFunds)\n",
+        ))
+        .unwrap();
+        assert_eq!(*projection.ident.fragment(), "AccountSummary");
+        assert_eq!(projection.fields.len(), 1);
+        let events: Vec<_> = projection
+            .events
+            .iter()
+            .map(|event| *event.fragment())
+            .collect();
+        assert_eq!(events, vec!["OpenedAccount", "DepositedFunds"]);
+        assert_eq!(*tail.fragment(), "\n");
+
+        assert!(parse_projection(Span::new("projection AccountSummary {}")).is_err());
+        assert!(
+            parse_projection(Span::new("projection AccountSummary {} on OpenedAccount")).is_err()
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use nom::{
+    branch::alt,
+    character::complete::char,
+    combinator::{map, opt, recognize, value},
+    multi::separated_list0,
+    sequence::{delimited, pair, preceded, separated_pair, tuple},
+};
+use nom_supreme::tag::complete::tag;
+
+use super::{
+    annotation::{parse_annotations, Annotation},
+    ident::{parse_camel_ident, parse_snake_ident},
+    parsers::{multinewline1, multispace_comment0, parse_docs, space_comment0, space_comment1},
+    IResult, Span,
+};
+
+/// Process manager reacting to events by invoking commands across aggregates.
+///
+/// ```text
+/// saga TransferFunds {
+///   on SentFunds -> Recipient.receive_funds
+///   on ReceivedFunds -> done
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Saga<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
+    pub ident: Span<'i>,
+    pub steps: Vec<SagaStep<'i>>,
+}
+
+pub fn parse_saga(input: Span) -> IResult<Span, Saga> {
+    // Steps are separated by commas, semicolons or new lines.
+    let separator = || {
+        alt((
+            recognize(tuple((
+                space_comment0,
+                alt((char(','), char(';'))),
+                multispace_comment0,
+            ))),
+            multinewline1,
+        ))
+    };
+    let ident_parser = preceded(pair(tag("saga"), space_comment1), parse_camel_ident);
+    let steps_parser = delimited(
+        pair(char('{'), multispace_comment0),
+        separated_list0(separator(), parse_saga_step),
+        tuple((opt(separator()), multispace_comment0, char('}'))),
+    );
+
+    map(
+        tuple((
+            parse_docs,
+            parse_annotations,
+            separated_pair(ident_parser, multispace_comment0, steps_parser),
+        )),
+        |(docs, annotations, (ident, steps))| Saga {
+            docs,
+            annotations,
+            ident,
+            steps,
+        },
+    )(input)
+}
+
+/// Reaction to an event, eg. `on SentFunds -> Recipient.receive_funds`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SagaStep<'i> {
+    pub event: Span<'i>,
+    pub target: SagaTarget<'i>,
+}
+
+pub fn parse_saga_step(input: Span) -> IResult<Span, SagaStep> {
+    map(
+        separated_pair(
+            preceded(pair(tag("on"), space_comment1), parse_camel_ident),
+            tuple((space_comment0, tag("->"), space_comment0)),
+            parse_saga_target,
+        ),
+        |(event, target)| SagaStep { event, target },
+    )(input)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SagaTarget<'i> {
+    /// Command of an aggregate, eg. `Recipient.receive_funds`.
+    Command {
+        aggregate: Span<'i>,
+        command: Span<'i>,
+    },
+    /// Completes the saga.
+    Done,
+}
+
+pub fn parse_saga_target(input: Span) -> IResult<Span, SagaTarget> {
+    let command_parser = map(
+        separated_pair(parse_camel_ident, char('.'), parse_snake_ident),
+        |(aggregate, command)| SagaTarget::Command { aggregate, command },
+    );
+
+    alt((value(SagaTarget::Done, tag("done")), command_parser))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_saga, SagaTarget, Span};
+
+    #[test]
+    fn it_parses_sagas() {
+        let (_, saga) = parse_saga(Span::new(
+            "saga TransferFunds { on SentFunds -> Recipient.receive_funds; on ReceivedFunds -> done }",
+        ))
+        .unwrap();
+        assert_eq!(*saga.ident.fragment(), "TransferFunds");
+        assert_eq!(saga.steps.len(), 2);
+        assert_eq!(*saga.steps[0].event.fragment(), "SentFunds");
+        assert!(matches!(
+            saga.steps[0].target,
+            SagaTarget::Command { aggregate, command }
+                if *aggregate.fragment() == "Recipient" && *command.fragment() == "receive_funds"
+        ));
+        assert_eq!(saga.steps[1].target, SagaTarget::Done);
+
+        assert!(parse_saga(Span::new(
+            "saga TransferFunds { on SentFunds -> receive_funds }"
+        ))
+        .is_err());
+        assert!(parse_saga(Span::new("saga TransferFunds { on SentFunds }")).is_err());
+    }
+}
@@ -1,6 +1,5 @@
 use nom::{
     branch::alt,
-    character::complete::{multispace0, multispace1},
     combinator::{map, value},
     multi::many0,
     sequence::delimited,
@@ -9,8 +8,16 @@ use semver::Version;
 
 use super::{
     aggregate::{parse_aggregate, Aggregate},
-    event::{parse_event, Event},
-    types::{parse_custom_type, CustomType},
+    event::{parse_command_error, parse_event, CommandError, Event},
+    import::{parse_import, Import},
+    package::parse_package,
+    parsers::{multispace_comment0, multispace_comment1},
+    projection::{parse_projection, Projection},
+    saga::{parse_saga, Saga},
+    types::{
+        parse_alias, parse_custom_scalar, parse_custom_type, parse_enum, parse_union, Alias,
+        CustomScalar, CustomType, Enum, Union,
+    },
     version::parse_version,
     IResult, Span,
 };
@@ -18,46 +25,98 @@ use super::{
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct Schema<'i> {
     pub versions: Vec<Version>,
+    pub packages: Vec<Span<'i>>,
+    pub imports: Vec<Import<'i>>,
     pub aggregates: Vec<Aggregate<'i>>,
+    pub projections: Vec<Projection<'i>>,
+    pub sagas: Vec<Saga<'i>>,
     pub events: Vec<Event<'i>>,
+    pub errors: Vec<CommandError<'i>>,
     pub types: Vec<CustomType<'i>>,
+    pub unions: Vec<Union<'i>>,
+    pub aliases: Vec<Alias<'i>>,
+    pub enums: Vec<Enum<'i>>,
+    pub scalars: Vec<CustomScalar<'i>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 enum SchemaType<'i> {
     Aggregate(Aggregate<'i>),
+    Projection(Projection<'i>),
+    Saga(Saga<'i>),
     Event(Event<'i>),
+    Error(CommandError<'i>),
     CustomType(CustomType<'i>),
+    Union(Union<'i>),
+    Alias(Alias<'i>),
+    Enum(Enum<'i>),
+    Scalar(CustomScalar<'i>),
     Version(Version),
+    Package(Span<'i>),
+    Import(Import<'i>),
     Noop,
 }
 
 pub fn parse_schema(input: Span) -> IResult<Span, Schema> {
     let (tail, schema_types) = delimited(
-        multispace0,
+        multispace_comment0,
         many0(alt((
-            value(SchemaType::Noop, multispace1),
+            value(SchemaType::Noop, multispace_comment1),
             map(parse_aggregate, SchemaType::Aggregate),
+            map(parse_projection, SchemaType::Projection),
+            map(parse_saga, SchemaType::Saga),
             map(parse_version, SchemaType::Version),
+            map(parse_package, SchemaType::Package),
+            map(parse_import, SchemaType::Import),
             map(parse_event, SchemaType::Event),
+            map(parse_command_error, SchemaType::Error),
             map(parse_custom_type, SchemaType::CustomType),
+            map(parse_union, SchemaType::Union),
+            map(parse_alias, SchemaType::Alias),
+            map(parse_enum, SchemaType::Enum),
+            map(parse_custom_scalar, SchemaType::Scalar),
         ))),
-        multispace0,
+        multispace_comment0,
     )(input)?;
 
-    let schema = schema_types
+    let mut schema = schema_types
         .into_iter()
         .fold(Schema::default(), |mut acc, item| {
             match item {
                 SchemaType::Aggregate(aggregate) => acc.aggregates.push(aggregate),
+                SchemaType::Projection(projection) => acc.projections.push(projection),
+                SchemaType::Saga(saga) => acc.sagas.push(saga),
                 SchemaType::Event(event) => acc.events.push(event),
+                SchemaType::Error(error) => acc.errors.push(error),
                 SchemaType::CustomType(ty) => acc.types.push(ty),
+                SchemaType::Union(ty) => acc.unions.push(ty),
+                SchemaType::Alias(ty) => acc.aliases.push(ty),
+                SchemaType::Enum(ty) => acc.enums.push(ty),
+                SchemaType::Scalar(ty) => acc.scalars.push(ty),
                 SchemaType::Version(version) => acc.versions.push(version),
+                SchemaType::Package(package) => acc.packages.push(package),
+                SchemaType::Import(import) => acc.imports.push(import),
                 SchemaType::Noop => {}
             }
 
             acc
         });
 
+    schema.set_package();
+
     Ok((tail, schema))
 }
+
+impl<'i> Schema<'i> {
+    /// Records the package of the file on each of its declarations, which keep it when imported.
+    pub fn set_package(&mut self) {
+        let package = self.packages.first().copied();
+        self.events.iter_mut().for_each(|ty| ty.package = package);
+        self.errors.iter_mut().for_each(|ty| ty.package = package);
+        self.types.iter_mut().for_each(|ty| ty.package = package);
+        self.unions.iter_mut().for_each(|ty| ty.package = package);
+        self.aliases.iter_mut().for_each(|ty| ty.package = package);
+        self.enums.iter_mut().for_each(|ty| ty.package = package);
+        self.scalars.iter_mut().for_each(|ty| ty.package = package);
+    }
+}
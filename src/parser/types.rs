@@ -1,66 +1,356 @@
+use std::path::Path;
+
 use nom::{
     branch::alt,
-    character::complete::{char, space0},
-    combinator::{map, opt, value},
-    sequence::{delimited, pair},
+    character::complete::{alphanumeric1, char},
+    combinator::{map, not, opt, value},
+    multi::separated_list1,
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
+    Parser,
 };
 use nom_supreme::tag::complete::tag;
 
 use super::{
-    event::Field, ident::parse_camel_ident, parsers::keyword_ident_structure, IResult, Span,
+    annotation::{parse_annotations, Annotation},
+    event::{parse_field, Field},
+    ident::{parse_camel_ident, parse_qualified_camel_ident},
+    parsers::{delimited_list0, multispace_comment0, parse_docs, space_comment0, space_comment1},
+    Error, IResult, Span,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CustomType<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
     pub ident: Span<'i>,
+    /// Type parameters of a generic type, eg. `T` in `type Page<T> { .. }`.
+    pub params: Vec<Span<'i>>,
     pub fields: Vec<Field<'i>>,
+    /// Package of the file declaring it, set once the whole file is parsed.
+    pub package: Option<Span<'i>>,
+    /// File the declaration was imported from, set when it is merged into an importing schema.
+    pub file: Option<&'i Path>,
+}
+
+pub fn parse_custom_type(input: Span) -> IResult<Span, CustomType> {
+    let ident_parser = preceded(
+        pair(alt((tag("type"), tag("struct"))), space_comment1),
+        pair(parse_camel_ident, opt(parse_type_params)),
+    );
+    let fields_parser = delimited_list0(char('{'), parse_field, char('}'));
+
+    map(
+        tuple((
+            parse_docs,
+            parse_annotations,
+            separated_pair(ident_parser, multispace_comment0, fields_parser),
+        )),
+        |(docs, annotations, ((ident, params), fields))| CustomType {
+            docs,
+            annotations,
+            ident,
+            params: params.unwrap_or_default(),
+            fields,
+            package: None,
+            file: None,
+        },
+    )(input)
+}
+
+/// Type parameters of a generic type, eg. `<K, V>`.
+pub fn parse_type_params(input: Span) -> IResult<Span, Vec<Span>> {
+    angle_bracketed(parse_camel_ident)(input)
+}
+
+/// Non-empty comma separated list between angle brackets, eg. `<Int, Money>`.
+fn angle_bracketed<'i, O, F>(item: F) -> impl FnMut(Span<'i>) -> IResult<Span<'i>, Vec<O>>
+where
+    F: Parser<Span<'i>, O, Error<Span<'i>>>,
+{
+    delimited(
+        pair(char('<'), space_comment0),
+        separated_list1(tuple((space_comment0, char(','), space_comment0)), item),
+        pair(space_comment0, char('>')),
+    )
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Union<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
+    pub ident: Span<'i>,
+    pub variants: Vec<UnionVariant<'i>>,
+    /// Package of the file declaring it, set once the whole file is parsed.
+    pub package: Option<Span<'i>>,
+    /// File the declaration was imported from, set when it is merged into an importing schema.
+    pub file: Option<&'i Path>,
+}
+
+pub fn parse_union(input: Span) -> IResult<Span, Union> {
+    let ident_parser = preceded(pair(tag("type"), space_comment1), parse_camel_ident);
+    let separator_parser = || tuple((multispace_comment0, char('|'), multispace_comment0));
+    let variants_parser = preceded(
+        opt(separator_parser()),
+        separated_list1(separator_parser(), parse_union_variant),
+    );
+
+    map(
+        tuple((
+            parse_docs,
+            parse_annotations,
+            separated_pair(
+                ident_parser,
+                tuple((multispace_comment0, char('='), multispace_comment0)),
+                variants_parser,
+            ),
+        )),
+        |(docs, annotations, (ident, variants))| Union {
+            docs,
+            annotations,
+            ident,
+            variants,
+            package: None,
+            file: None,
+        },
+    )(input)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnionVariant<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
+    pub ident: Span<'i>,
+    pub fields: Vec<Field<'i>>,
+}
+
+pub fn parse_union_variant(input: Span) -> IResult<Span, UnionVariant> {
+    let fields_parser = delimited_list0(char('{'), parse_field, char('}'));
+
+    map(
+        tuple((
+            parse_docs,
+            parse_annotations,
+            separated_pair(parse_camel_ident, multispace_comment0, fields_parser),
+        )),
+        |(docs, annotations, (ident, fields))| UnionVariant {
+            docs,
+            annotations,
+            ident,
+            fields,
+        },
+    )(input)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alias<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
+    pub ident: Span<'i>,
+    pub ty: Type<'i>,
+    /// Package of the file declaring it, set once the whole file is parsed.
+    pub package: Option<Span<'i>>,
+    /// File the declaration was imported from, set when it is merged into an importing schema.
+    pub file: Option<&'i Path>,
+}
+
+pub fn parse_alias(input: Span) -> IResult<Span, Alias> {
+    let ident_parser = preceded(pair(tag("type"), space_comment1), parse_camel_ident);
+
+    map(
+        tuple((
+            parse_docs,
+            parse_annotations,
+            terminated(
+                separated_pair(
+                    ident_parser,
+                    tuple((multispace_comment0, char('='), multispace_comment0)),
+                    parse_type,
+                ),
+                opt(pair(space_comment0, char(';'))),
+            ),
+        )),
+        |(docs, annotations, (ident, ty))| Alias {
+            docs,
+            annotations,
+            ident,
+            ty,
+            package: None,
+            file: None,
+        },
+    )(input)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Enum<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
+    pub ident: Span<'i>,
+    pub variants: Vec<EnumVariant<'i>>,
+    /// Package of the file declaring it, set once the whole file is parsed.
+    pub package: Option<Span<'i>>,
+    /// File the declaration was imported from, set when it is merged into an importing schema.
+    pub file: Option<&'i Path>,
+}
+
+pub fn parse_enum(input: Span) -> IResult<Span, Enum> {
+    let ident_parser = preceded(pair(tag("enum"), space_comment1), parse_camel_ident);
+    let variants_parser = delimited_list0(char('{'), parse_enum_variant, char('}'));
+
+    map(
+        tuple((
+            parse_docs,
+            parse_annotations,
+            separated_pair(ident_parser, multispace_comment0, variants_parser),
+        )),
+        |(docs, annotations, (ident, variants))| Enum {
+            docs,
+            annotations,
+            ident,
+            variants,
+            package: None,
+            file: None,
+        },
+    )(input)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnumVariant<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
+    pub ident: Span<'i>,
+}
+
+pub fn parse_enum_variant(input: Span) -> IResult<Span, EnumVariant> {
+    map(
+        tuple((parse_docs, parse_annotations, parse_camel_ident)),
+        |(docs, annotations, ident)| EnumVariant {
+            docs,
+            annotations,
+            ident,
+        },
+    )(input)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomScalar<'i> {
+    pub docs: Vec<Span<'i>>,
+    pub annotations: Vec<Annotation<'i>>,
+    pub ident: Span<'i>,
+    pub base: Option<Scalar>,
+    /// Package of the file declaring it, set once the whole file is parsed.
+    pub package: Option<Span<'i>>,
+    /// File the declaration was imported from, set when it is merged into an importing schema.
+    pub file: Option<&'i Path>,
 }
 
-pub fn parse_custom_type(input: Span) -> IResult<Span, CustomType<'_>> {
-    map(keyword_ident_structure("type"), |(ident, fields)| {
-        CustomType { ident, fields }
-    })(input)
+pub fn parse_custom_scalar(input: Span) -> IResult<Span, CustomScalar> {
+    let ident_parser = preceded(pair(tag("scalar"), space_comment1), parse_camel_ident);
+    let base_parser = preceded(
+        tuple((space_comment0, char(':'), space_comment0)),
+        parse_scalar,
+    );
+
+    map(
+        tuple((
+            parse_docs,
+            parse_annotations,
+            ident_parser,
+            opt(base_parser),
+        )),
+        |(docs, annotations, ident, base)| CustomScalar {
+            docs,
+            annotations,
+            ident,
+            base,
+            package: None,
+            file: None,
+        },
+    )(input)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Type<'i> {
     Single(OptionalOrRequiredType<'i>),
+    /// Array of any type, eg. `[[Int?]]?` or `[{String: Int}]`.
     Array {
-        inner: OptionalOrRequiredType<'i>,
+        inner: Box<Type<'i>>,
+        optional: bool,
+    },
+    /// Map of any type by a scalar or user type key, eg. `{String: [Int]}`.
+    Map {
+        key: ScalarOrUserType<'i>,
+        value: Box<Type<'i>>,
         optional: bool,
     },
 }
 
-pub fn parse_type(input: Span) -> IResult<Span, Type<'_>> {
+pub fn parse_type(input: Span) -> IResult<Span, Type> {
     let single_type_parser = map(parse_optional_or_required_type, Type::Single);
-    let array_type_parser = map(
+    let map_type_parser = map(
         pair(
             delimited(
-                pair(tag("["), space0),
-                parse_optional_or_required_type,
-                pair(space0, tag("]")),
+                pair(tag("{"), space_comment0),
+                separated_pair(
+                    parse_scalar_or_user_type,
+                    tuple((space_comment0, tag(":"), space_comment0)),
+                    parse_type,
+                ),
+                pair(space_comment0, tag("}")),
             ),
             opt(char('?')),
         ),
-        |(ty, optional)| Type::Array {
-            inner: ty,
+        |((key, value), optional)| Type::Map {
+            key,
+            value: Box::new(value),
             optional: optional.is_some(),
         },
     );
-    alt((single_type_parser, array_type_parser))(input)
+    alt((single_type_parser, parse_array_type, map_type_parser))(input)
+}
+
+/// Array type, whose items can be of any type, including arrays and maps.
+pub fn parse_array_type(input: Span) -> IResult<Span, Type> {
+    map(
+        pair(
+            delimited(
+                pair(tag("["), space_comment0),
+                parse_type,
+                pair(space_comment0, tag("]")),
+            ),
+            opt(char('?')),
+        ),
+        |(ty, optional)| Type::Array {
+            inner: Box::new(ty),
+            optional: optional.is_some(),
+        },
+    )(input)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ScalarOrUserType<'i> {
     Scalar(Scalar),
     UserDefined(Span<'i>),
+    /// Application of a generic type, eg. `Change<Money>`.
+    ///
+    /// Type arguments are parsed as any type, though only single required types are valid.
+    Generic {
+        ident: Span<'i>,
+        args: Vec<Type<'i>>,
+    },
 }
 
-pub fn parse_scalar_or_user_type(input: Span) -> IResult<Span, ScalarOrUserType<'_>> {
+pub fn parse_scalar_or_user_type(input: Span) -> IResult<Span, ScalarOrUserType> {
     let scalar_parser = map(parse_scalar, ScalarOrUserType::Scalar);
-    let user_defined_parser = map(parse_camel_ident, |ident| {
-        ScalarOrUserType::UserDefined(ident)
-    });
+    let user_defined_parser = map(
+        pair(
+            parse_qualified_camel_ident,
+            opt(angle_bracketed(parse_type)),
+        ),
+        |(ident, args)| match args {
+            Some(args) => ScalarOrUserType::Generic { ident, args },
+            None => ScalarOrUserType::UserDefined(ident),
+        },
+    );
 
     alt((scalar_parser, user_defined_parser))(input)
 }
@@ -71,7 +361,7 @@ pub enum OptionalOrRequiredType<'i> {
     Required(ScalarOrUserType<'i>),
 }
 
-pub fn parse_optional_or_required_type(input: Span) -> IResult<Span, OptionalOrRequiredType<'_>> {
+pub fn parse_optional_or_required_type(input: Span) -> IResult<Span, OptionalOrRequiredType> {
     let mut optional_or_required_parser = map(
         pair(parse_scalar_or_user_type, opt(char('?'))),
         |(scalar_or_user_type, optional)| {
@@ -91,16 +381,30 @@ pub enum Scalar {
     String,
     /// 32-bit signed integer
     Int,
+    /// 32-bit unsigned integer
+    UInt,
     /// 64-bit signed integer
     Long,
+    /// 64-bit unsigned integer
+    ULong,
     /// 32-bit float
     Float,
     /// 64-bit float
     Double,
+    /// Arbitrary-precision decimal
+    Decimal,
     /// Binary value
     Bool,
     /// Sequence of 8-bit unsigned bytes
     Bytes,
+    /// Instant in time, in UTC
+    Timestamp,
+    /// Calendar date, without a time zone
+    Date,
+    /// Length of time
+    Duration,
+    /// Universally unique identifier
+    Uuid,
 }
 
 pub fn parse_scalar_string(input: Span) -> IResult<Span, Scalar> {
@@ -112,9 +416,17 @@ pub fn parse_scalar_int(input: Span) -> IResult<Span, Scalar> {
 }
 
 pub fn parse_scalar_uint(input: Span) -> IResult<Span, Scalar> {
+    value(Scalar::UInt, tag("UInt"))(input)
+}
+
+pub fn parse_scalar_long(input: Span) -> IResult<Span, Scalar> {
     value(Scalar::Long, tag("Long"))(input)
 }
 
+pub fn parse_scalar_ulong(input: Span) -> IResult<Span, Scalar> {
+    value(Scalar::ULong, tag("ULong"))(input)
+}
+
 pub fn parse_scalar_float(input: Span) -> IResult<Span, Scalar> {
     value(Scalar::Float, tag("Float"))(input)
 }
@@ -123,6 +435,10 @@ pub fn parse_scalar_double(input: Span) -> IResult<Span, Scalar> {
     value(Scalar::Double, tag("Double"))(input)
 }
 
+pub fn parse_scalar_decimal(input: Span) -> IResult<Span, Scalar> {
+    value(Scalar::Decimal, tag("Decimal"))(input)
+}
+
 pub fn parse_scalar_bool(input: Span) -> IResult<Span, Scalar> {
     value(Scalar::Bool, tag("Bool"))(input)
 }
@@ -131,14 +447,172 @@ pub fn parse_scalar_bytes(input: Span) -> IResult<Span, Scalar> {
     value(Scalar::Bytes, tag("Bytes"))(input)
 }
 
+pub fn parse_scalar_timestamp(input: Span) -> IResult<Span, Scalar> {
+    value(Scalar::Timestamp, tag("Timestamp"))(input)
+}
+
+pub fn parse_scalar_date(input: Span) -> IResult<Span, Scalar> {
+    value(Scalar::Date, tag("Date"))(input)
+}
+
+pub fn parse_scalar_duration(input: Span) -> IResult<Span, Scalar> {
+    value(Scalar::Duration, tag("Duration"))(input)
+}
+
+pub fn parse_scalar_uuid(input: Span) -> IResult<Span, Scalar> {
+    value(Scalar::Uuid, tag("Uuid"))(input)
+}
+
+/// Scalar type, which must not be followed by further identifier characters.
 pub fn parse_scalar(input: Span) -> IResult<Span, Scalar> {
-    alt((
-        parse_scalar_string,
-        parse_scalar_int,
-        parse_scalar_uint,
-        parse_scalar_float,
-        parse_scalar_double,
-        parse_scalar_bool,
-        parse_scalar_bytes,
-    ))(input)
+    terminated(
+        alt((
+            parse_scalar_string,
+            parse_scalar_int,
+            parse_scalar_uint,
+            parse_scalar_long,
+            parse_scalar_ulong,
+            parse_scalar_float,
+            parse_scalar_double,
+            parse_scalar_decimal,
+            parse_scalar_bool,
+            parse_scalar_bytes,
+            parse_scalar_timestamp,
+            parse_scalar_date,
+            parse_scalar_duration,
+            parse_scalar_uuid,
+        )),
+        not(alphanumeric1),
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_custom_type, parse_type, OptionalOrRequiredType, Scalar, ScalarOrUserType, Span, Type,
+    };
+
+    #[test]
+    fn it_parses_map_types() {
+        let (_, ty) = parse_type(Span::new("{String: Int}")).unwrap();
+        assert_eq!(
+            ty,
+            Type::Map {
+                key: ScalarOrUserType::Scalar(Scalar::String),
+                value: Box::new(Type::Single(OptionalOrRequiredType::Required(
+                    ScalarOrUserType::Scalar(Scalar::Int)
+                ))),
+                optional: false,
+            }
+        );
+
+        let (_, ty) = parse_type(Span::new("{ String : Balance? }?")).unwrap();
+        assert!(matches!(
+            ty,
+            Type::Map {
+                key: ScalarOrUserType::Scalar(Scalar::String),
+                value,
+                optional: true,
+            } if matches!(
+                *value,
+                Type::Single(OptionalOrRequiredType::Optional(ScalarOrUserType::UserDefined(value)))
+                    if *value.fragment() == "Balance"
+            )
+        ));
+
+        let (_, ty) = parse_type(Span::new("{String: [{Int: Bool}]?}")).unwrap();
+        assert!(matches!(
+            ty,
+            Type::Map { value, optional: false, .. } if matches!(
+                *value,
+                Type::Array { ref inner, optional: true } if matches!(**inner, Type::Map { .. })
+            )
+        ));
+
+        assert!(parse_type(Span::new("{String?: Int}")).is_err());
+    }
+
+    #[test]
+    fn it_parses_nested_array_types() {
+        let (_, ty) = parse_type(Span::new("[[String?]]?")).unwrap();
+        assert_eq!(
+            ty,
+            Type::Array {
+                inner: Box::new(Type::Array {
+                    inner: Box::new(Type::Single(OptionalOrRequiredType::Optional(
+                        ScalarOrUserType::Scalar(Scalar::String)
+                    ))),
+                    optional: false,
+                }),
+                optional: true,
+            }
+        );
+
+        let (_, ty) = parse_type(Span::new("[ [ [Int] ]? ]")).unwrap();
+        assert!(matches!(
+            ty,
+            Type::Array { inner, optional: false } if matches!(
+                *inner,
+                Type::Array { ref inner, optional: true } if matches!(**inner, Type::Array { .. })
+            )
+        ));
+
+        assert!(parse_type(Span::new("[[Int]")).is_err());
+
+        let (_, ty) = parse_type(Span::new("[{String: Int}]")).unwrap();
+        assert!(matches!(
+            ty,
+            Type::Array { inner, optional: false } if matches!(*inner, Type::Map { .. })
+        ));
+    }
+
+    #[test]
+    fn it_parses_generic_types() {
+        let (_, ty) = parse_custom_type(Span::new("type Change<T, At> { new: T }")).unwrap();
+        let params: Vec<_> = ty.params.iter().map(|param| *param.fragment()).collect();
+        assert_eq!(params, vec!["T", "At"]);
+
+        let (_, ty) = parse_type(Span::new("Page< Change<Money>, Int >?")).unwrap();
+        let Type::Single(OptionalOrRequiredType::Optional(ScalarOrUserType::Generic {
+            ident,
+            args,
+        })) = ty
+        else {
+            panic!("expected optional generic type, got {ty:?}");
+        };
+        assert_eq!(*ident.fragment(), "Page");
+        assert!(matches!(
+            args.as_slice(),
+            [
+                Type::Single(OptionalOrRequiredType::Required(ScalarOrUserType::Generic { ident, args })),
+                Type::Single(OptionalOrRequiredType::Required(ScalarOrUserType::Scalar(Scalar::Int))),
+            ] if *ident.fragment() == "Change" && args.len() == 1
+        ));
+
+        assert!(parse_custom_type(Span::new("type Page<> { items: [String] }")).is_err());
+        let (tail, ty) = parse_type(Span::new("Page<[Money], Money?, {String: Int}>")).unwrap();
+        assert!(tail.is_empty());
+        assert!(matches!(
+            ty,
+            Type::Single(OptionalOrRequiredType::Required(ScalarOrUserType::Generic { args, .. }))
+                if matches!(
+                    args.as_slice(),
+                    [
+                        Type::Array { .. },
+                        Type::Single(OptionalOrRequiredType::Optional(_)),
+                        Type::Map { .. },
+                    ]
+                )
+        ));
+    }
+
+    #[test]
+    fn it_parses_user_types_prefixed_by_scalars() {
+        let (_, ty) = parse_type(Span::new("Stringy?")).unwrap();
+        assert!(matches!(
+            ty,
+            Type::Single(OptionalOrRequiredType::Optional(ScalarOrUserType::UserDefined(ident)))
+                if *ident.fragment() == "Stringy"
+        ));
+    }
 }
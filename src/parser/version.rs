@@ -2,28 +2,22 @@ use std::str::FromStr;
 
 use nom::{
     bytes::complete::is_not,
-    character::complete::{char, space0},
+    character::complete::char,
     combinator::{map_res, recognize},
     sequence::{delimited, preceded, tuple},
 };
 use nom_supreme::tag::complete::tag;
 use semver::Version;
 
-use super::{event::Field, IResult, Span};
-
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct CustomType<'i> {
-    pub ident: Span<'i>,
-    pub fields: Vec<Field<'i>>,
-}
+use super::{parsers::space_comment0, IResult, Span};
 
 pub fn parse_version(input: Span) -> IResult<Span, Version> {
     map_res(
         preceded(
-            tuple((tag("version"), space0, char('='), space0)),
+            tuple((tag("version"), space_comment0, char('='), space_comment0)),
             delimited(char('"'), recognize(is_not("\"")), char('"')),
         ),
-        Version::from_str,
+        |version: Span| Version::from_str(version.fragment()),
     )(input)
 }
 
@@ -31,18 +25,20 @@ pub fn parse_version(input: Span) -> IResult<Span, Version> {
 mod tests {
     use semver::Version;
 
-    use super::parse_version;
+    use super::{parse_version, Span};
 
     #[test]
     fn version() {
-        assert_eq!(
-            parse_version(r#"version="0.1.0""#).unwrap(),
-            ("", Version::new(0, 1, 0))
-        );
-        assert_eq!(
-            parse_version(r#"version   =  "123.456.789" hello"#).unwrap(),
-            (" hello", Version::new(123, 456, 789))
-        );
-        assert!(parse_version(r#"version="0.1hi0""#).is_err());
+        let (tail, version) = parse_version(Span::new(r#"version="0.1.0""#)).unwrap();
+        assert_eq!(*tail.fragment(), "");
+        assert_eq!(version, Version::new(0, 1, 0));
+
+        let (tail, version) =
+            parse_version(Span::new(r#"version   =  "123.456.789" hello"#)).unwrap();
+        assert_eq!(*tail.fragment(), " hello");
+        assert_eq!(tail.location_offset(), 26);
+        assert_eq!(version, Version::new(123, 456, 789));
+
+        assert!(parse_version(Span::new(r#"version="0.1hi0""#)).is_err());
     }
 }
@@ -1,65 +1,256 @@
 use std::{
-    collections::HashMap,
-    fs, path,
-    str::{self, FromStr},
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt, path, str,
 };
 
+use regex::Regex;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 
 use crate::Error;
 
-/// Schema definition including aggregate, commands, events & custom types.
+mod import;
+
+/// Schema definition including aggregates, commands, events & custom types.
+///
+/// Events, errors and user defined types are keyed by their qualified name, such as
+/// `common.Money`, which is their name alone if the file declaring them has no package.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Schema {
     pub version: Version,
-    pub aggregate: Aggregate,
+    /// Package declared with `package banking.accounts`, qualifying the names of its declarations.
+    pub package: Option<String>,
+    pub aggregates: HashMap<String, Aggregate>,
+    pub projections: HashMap<String, Projection>,
+    pub sagas: HashMap<String, Saga>,
     pub events: HashMap<String, Event>,
+    pub errors: HashMap<String, CommandError>,
     pub types: HashMap<String, CustomType>,
+    pub unions: HashMap<String, Union>,
+    pub aliases: HashMap<String, Alias>,
+    pub enums: HashMap<String, Enum>,
+    pub scalars: HashMap<String, CustomScalar>,
 }
 
 impl Schema {
     pub fn validate_parsed_schema(schema: crate::parser::schema::Schema) -> Result<Self, Error> {
-        let schema_types: HashMap<_, _> = schema
+        if let Some(import) = schema.imports.first() {
+            return Err(Error::UnsupportedImport {
+                position: import.path.into(),
+            });
+        }
+
+        Schema::validate(schema, true)
+    }
+
+    /// Validates a schema whose imports have been resolved.
+    ///
+    /// Imported files need not declare a version, in which case it defaults to `0.0.0`.
+    fn validate(
+        schema: crate::parser::schema::Schema,
+        requires_version: bool,
+    ) -> Result<Self, Error> {
+        let declared_types: Vec<_> = schema
             .types
             .iter()
-            .map(|ty| (ty.ident, &ty.fields))
+            .map(|ty| (ty.ident, UserType::Custom(ty)))
+            .chain(
+                schema
+                    .unions
+                    .iter()
+                    .map(|ty| (ty.ident, UserType::Union(ty))),
+            )
+            .chain(
+                schema
+                    .aliases
+                    .iter()
+                    .map(|ty| (ty.ident, UserType::Alias(ty))),
+            )
+            .chain(schema.enums.iter().map(|ty| (ty.ident, UserType::Enum(ty))))
+            .chain(
+                schema
+                    .scalars
+                    .iter()
+                    .map(|ty| (ty.ident, UserType::Scalar(ty))),
+            )
             .collect();
 
+        let declared =
+            declared_types
+                .iter()
+                .copied()
+                .try_fold(HashMap::new(), |mut acc, (ident, ty)| {
+                    if acc
+                        .insert(qualified_name(ty.package(), ident.fragment()), ty)
+                        .is_some()
+                    {
+                        let name = ident.to_string();
+                        let position = ident.into();
+                        return Err(in_declaring_file(
+                            ty.file(),
+                            match ty {
+                                UserType::Enum(_) => Error::DuplicateEnum { name, position },
+                                UserType::Scalar(_) => Error::DuplicateScalar { name, position },
+                                _ => Error::DuplicateCustomType { name, position },
+                            },
+                        ));
+                    }
+
+                    Ok(acc)
+                })?;
+
+        let user_types = UserTypes {
+            declared: &declared,
+            package: None,
+            params: vec![],
+        };
+        check_recursive_types(&declared_types, &user_types)?;
+
         let types = schema
             .types
             .iter()
-            .try_fold(HashMap::new(), |mut acc, ty| {
-                let name = ty.ident.to_string();
-                let custom_type = CustomType::from_custom_type(&schema_types, ty)?;
-                if acc.insert(name.clone(), custom_type).is_some() {
-                    return Err(Error::DuplicateCustomType(name));
-                }
+            .map(|ty| {
+                let custom_type =
+                    CustomType::from_custom_type(&user_types.in_package(ty.package), ty)
+                        .map_err(|err| in_declaring_file(ty.file, err))?;
+                Ok((custom_type.qualified_name.clone(), custom_type))
+            })
+            .collect::<Result<_, Error>>()?;
 
-                Ok(acc)
-            })?;
+        let unions = schema
+            .unions
+            .iter()
+            .map(|ty| {
+                let union = Union::from_union(&user_types.in_package(ty.package), ty)
+                    .map_err(|err| in_declaring_file(ty.file, err))?;
+                Ok((union.qualified_name.clone(), union))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let aliases = schema
+            .aliases
+            .iter()
+            .map(|ty| {
+                let alias = Alias::from_alias(&user_types.in_package(ty.package), ty)
+                    .map_err(|err| in_declaring_file(ty.file, err))?;
+                Ok((alias.qualified_name.clone(), alias))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let enums = schema
+            .enums
+            .iter()
+            .map(|ty| {
+                let enum_type =
+                    Enum::from_enum(ty).map_err(|err| in_declaring_file(ty.file, err))?;
+                Ok((enum_type.qualified_name.clone(), enum_type))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        let scalars = schema
+            .scalars
+            .iter()
+            .map(|ty| {
+                let scalar = CustomScalar::from_custom_scalar(ty)
+                    .map_err(|err| in_declaring_file(ty.file, err))?;
+                Ok((scalar.qualified_name.clone(), scalar))
+            })
+            .collect::<Result<_, Error>>()?;
 
         let events = schema
             .events
             .iter()
             .try_fold(HashMap::new(), |mut acc, event| {
                 let name = event.ident.to_string();
-                let event = Event::from_event(&schema_types, event)?;
-                if acc.insert(name.clone(), event).is_some() {
-                    return Err(Error::DuplicateEvent(name));
+                let position = event.ident.into();
+                let file = event.file;
+                let event = Event::from_event(&user_types.in_package(event.package), event)
+                    .map_err(|err| in_declaring_file(file, err))?;
+                if acc.insert(event.qualified_name.clone(), event).is_some() {
+                    return Err(in_declaring_file(
+                        file,
+                        Error::DuplicateEvent { name, position },
+                    ));
                 }
 
                 Ok(acc)
             })?;
 
-        let aggregate = if schema.aggregates.len() > 1 {
-            return Err(Error::MultipleAggregates);
-        } else {
-            match schema.aggregates.first() {
-                Some(aggregate) => Aggregate::from_aggregate(aggregate, &schema_types, &events)?,
-                None => return Err(Error::MissingAggregate),
-            }
-        };
+        // Aggregates, projections and sagas refer to declarations from the package of the file.
+        let user_types = user_types.in_package(schema.packages.first().copied());
+
+        let projections =
+            schema
+                .projections
+                .iter()
+                .try_fold(HashMap::new(), |mut acc, projection| {
+                    let name = projection.ident.to_string();
+                    let position = projection.ident.into();
+                    let projection = Projection::from_projection(&user_types, projection, &events)?;
+                    if acc.insert(name.clone(), projection).is_some() {
+                        return Err(Error::DuplicateProjection { name, position });
+                    }
+
+                    Ok(acc)
+                })?;
+
+        let errors = schema
+            .errors
+            .iter()
+            .try_fold(HashMap::new(), |mut acc, error| {
+                let name = error.ident.to_string();
+                let position = error.ident.into();
+                let file = error.file;
+                let error =
+                    CommandError::from_command_error(&user_types.in_package(error.package), error)
+                        .map_err(|err| in_declaring_file(file, err))?;
+                if acc.insert(error.qualified_name.clone(), error).is_some() {
+                    return Err(in_declaring_file(
+                        file,
+                        Error::DuplicateError { name, position },
+                    ));
+                }
+
+                Ok(acc)
+            })?;
+
+        let aggregates =
+            schema
+                .aggregates
+                .iter()
+                .try_fold(HashMap::new(), |mut acc, aggregate| {
+                    let name = aggregate.ident.to_string();
+                    if acc
+                        .insert(
+                            name.clone(),
+                            Aggregate::from_aggregate(aggregate, &user_types, &events, &errors)?,
+                        )
+                        .is_some()
+                    {
+                        return Err(Error::DuplicateAggregate {
+                            name,
+                            position: aggregate.ident.into(),
+                        });
+                    }
+
+                    Ok(acc)
+                })?;
+
+        let sagas = schema
+            .sagas
+            .iter()
+            .try_fold(HashMap::new(), |mut acc, saga| {
+                let name = saga.ident.to_string();
+                let position = saga.ident.into();
+                let saga =
+                    Saga::from_saga(saga, user_types.package.as_deref(), &events, &aggregates)?;
+                if acc.insert(name.clone(), saga).is_some() {
+                    return Err(Error::DuplicateSaga { name, position });
+                }
+
+                Ok(acc)
+            })?;
 
         let version = if schema.versions.len() > 1 {
             return Err(Error::MultipleVersions);
@@ -68,20 +259,37 @@ impl Schema {
                 .versions
                 .into_iter()
                 .next()
+                .or_else(|| (!requires_version).then(|| Version::new(0, 0, 0)))
                 .ok_or(Error::MissingVersion)?
         };
 
+        let package = if schema.packages.len() > 1 {
+            return Err(Error::MultiplePackages);
+        } else {
+            schema.packages.first().map(|package| package.to_string())
+        };
+
         Ok(Schema {
             version,
-            aggregate,
+            package,
+            aggregates,
+            projections,
+            sagas,
             events,
+            errors,
             types,
+            unions,
+            aliases,
+            enums,
+            scalars,
         })
     }
 
+    /// Loads a schema file, resolving its imports relative to the importing file.
+    ///
+    /// Errors are wrapped in [`Error::File`] with the path of the file they occurred in.
     pub fn from_file<P: AsRef<path::Path>>(path: P) -> Result<Self, Error> {
-        let content = fs::read_to_string(path)?;
-        Schema::from_str(&content)
+        import::load_schema(path.as_ref())
     }
 }
 
@@ -93,27 +301,67 @@ impl str::FromStr for Schema {
     }
 }
 
-/// Aggregate definition with name and commands.
+/// Aggregate definition with name, state and commands.
 ///
 /// ```text
-/// aggregate BankAccount  {
+/// aggregate BankAccount(id: Uuid) {
+///   state { balance: Float }
 ///   open_account(user: User, initial_balance: Float?): OpenedAccount
-///   make_transaction(amount: Float?): (DepositedFunds | WithdrewFunds)
+///   make_transaction(amount: Float?): (DepositedFunds | WithdrewFunds) ! AccountClosed
 /// }
 /// ```
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Aggregate {
     pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub identity: Option<Identity>,
+    /// Fields of the aggregate's `state` block, empty if it declares none.
+    pub state: HashMap<String, Field>,
     pub commands: HashMap<String, Command>,
+    pub position: Position,
 }
 
 impl Aggregate {
     fn from_aggregate(
         aggregate: &crate::parser::aggregate::Aggregate,
-        custom_types: &HashMap<&str, &Vec<crate::parser::event::Field>>,
+        user_types: &UserTypes<'_, '_, '_>,
         events: &HashMap<String, Event>,
+        errors: &HashMap<String, CommandError>,
     ) -> Result<Self, Error> {
         let name = aggregate.ident.to_string();
+        let identity = aggregate
+            .identity
+            .as_ref()
+            .map(|identity| Identity::from_identity(user_types, &name, identity))
+            .transpose()?;
+        if let Some(state) = aggregate.states.get(1) {
+            return Err(Error::DuplicateState {
+                aggregate: name,
+                position: state.keyword.into(),
+            });
+        }
+        let state = aggregate
+            .states
+            .iter()
+            .flat_map(|state| &state.fields)
+            .try_fold(HashMap::new(), |mut acc, field| {
+                if acc
+                    .insert(
+                        field.ident.to_string(),
+                        Field::from_field(user_types, field)?,
+                    )
+                    .is_some()
+                {
+                    return Err(Error::DuplicateStateField {
+                        aggregate: name.clone(),
+                        field: field.ident.to_string(),
+                        position: field.ident.into(),
+                    });
+                }
+
+                Ok(acc)
+            })?;
         let commands = aggregate
             .commands
             .iter()
@@ -121,35 +369,342 @@ impl Aggregate {
                 if acc
                     .insert(
                         command.ident.to_string(),
-                        Command::from_command(command, custom_types, events)?,
+                        Command::from_command(command, user_types, events, errors)?,
+                    )
+                    .is_some()
+                {
+                    return Err(Error::DuplicateCommand {
+                        name: command.ident.to_string(),
+                        position: command.ident.into(),
+                    });
+                }
+
+                Ok(acc)
+            })?;
+
+        Ok(Aggregate {
+            name,
+            docs: parse_docs(&aggregate.docs),
+            annotations: parse_annotations(&aggregate.annotations)?,
+            identity,
+            state,
+            commands,
+            position: aggregate.ident.into(),
+        })
+    }
+}
+
+/// Key identifying each instance of an aggregate, such as its event stream.
+/// - `aggregate BankAccount(id: Uuid)`
+/// - `aggregate BankAccount keyed by account_number: String`
+///
+/// Keys are a required `String`, `Int`, `UInt`, `Long`, `ULong` or `Uuid`, or a custom scalar which
+/// is based on one of them or on no scalar at all. Aliases of these are allowed too.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+    pub name: String,
+    pub ty: TypeRef,
+    pub position: Position,
+}
+
+impl Identity {
+    fn from_identity(
+        user_types: &UserTypes<'_, '_, '_>,
+        aggregate: &str,
+        identity: &crate::parser::aggregate::Identity,
+    ) -> Result<Self, Error> {
+        let ty = RepeatableType::from_type(user_types, &identity.ty)?;
+        let is_key = |scalar: Scalar| {
+            matches!(
+                scalar,
+                Scalar::String
+                    | Scalar::Int
+                    | Scalar::UInt
+                    | Scalar::Long
+                    | Scalar::ULong
+                    | Scalar::Uuid
+            )
+        };
+        match (&ty, ty.resolve_aliases(user_types)?) {
+            (
+                RepeatableType::Single(TypeOpt::Required(ty)),
+                RepeatableType::Single(TypeOpt::Required(key)),
+            ) if key
+                .scalar(user_types)
+                .map_or(matches!(key, TypeRef::CustomScalar(_)), is_key) =>
+            {
+                Ok(Identity {
+                    name: identity.ident.to_string(),
+                    ty: ty.clone(),
+                    position: identity.ident.into(),
+                })
+            }
+            _ => Err(Error::InvalidIdentity {
+                aggregate: aggregate.to_string(),
+                ty: ty.to_string(),
+                position: identity.ident.into(),
+            }),
+        }
+    }
+}
+
+/// Projection definition with name, fields and the events it subscribes to, declaring a read model.
+///
+/// ```text
+/// projection AccountSummary {
+///   balance: Float
+/// } on (OpenedAccount, DepositedFunds, WithdrewFunds)
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Projection {
+    pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub fields: HashMap<String, Field>,
+    /// Qualified names of the events the projection subscribes to, in the order they are listed,
+    /// as keyed in [`Schema::events`].
+    pub events: Vec<String>,
+    pub position: Position,
+}
+
+impl Projection {
+    fn from_projection(
+        user_types: &UserTypes<'_, '_, '_>,
+        projection: &crate::parser::projection::Projection,
+        events: &HashMap<String, Event>,
+    ) -> Result<Self, Error> {
+        let name = projection.ident.to_string();
+        let fields = projection
+            .fields
+            .iter()
+            .try_fold(HashMap::new(), |mut acc, field| {
+                if acc
+                    .insert(
+                        field.ident.to_string(),
+                        Field::from_field(user_types, field)?,
                     )
                     .is_some()
                 {
-                    return Err(Error::DuplicateCommand(command.ident.to_string()));
+                    return Err(Error::DuplicateProjectionField {
+                        projection: name.clone(),
+                        field: field.ident.to_string(),
+                        position: field.ident.into(),
+                    });
                 }
 
                 Ok(acc)
             })?;
 
-        Ok(Aggregate { name, commands })
+        if projection.events.is_empty() {
+            return Err(Error::EmptyProjection {
+                name,
+                position: projection.ident.into(),
+            });
+        }
+        let events =
+            projection
+                .events
+                .iter()
+                .try_fold(Vec::<String>::new(), |mut acc, event_name| {
+                    let event = lookup(events, user_types.package.as_deref(), event_name)?
+                        .ok_or_else(|| Error::EventNotDefined {
+                            name: event_name.to_string(),
+                            position: (*event_name).into(),
+                        })?;
+                    if acc.contains(&event.qualified_name) {
+                        return Err(Error::DuplicateSubscription {
+                            projection: name.clone(),
+                            event: event_name.to_string(),
+                            position: (*event_name).into(),
+                        });
+                    }
+
+                    acc.push(event.qualified_name.clone());
+
+                    Ok(acc)
+                })?;
+
+        Ok(Projection {
+            name,
+            docs: parse_docs(&projection.docs),
+            annotations: parse_annotations(&projection.annotations)?,
+            fields,
+            events,
+            position: projection.ident.into(),
+        })
+    }
+}
+
+/// Saga definition, a process manager reacting to events by invoking commands across aggregates.
+///
+/// ```text
+/// saga TransferFunds {
+///   on SentFunds -> Recipient.receive_funds
+///   on ReceivedFunds -> done
+/// }
+/// ```
+///
+/// Its steps form a workflow graph, where each step's command leads to the steps reacting to the
+/// events the command results in, see [`Saga::next_steps`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Saga {
+    pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub steps: Vec<SagaStep>,
+    pub position: Position,
+}
+
+impl Saga {
+    /// Steps reacting to the events resulted by the command of a step, as declared in the schema
+    /// the saga belongs to.
+    pub fn next_steps(&self, schema: &Schema, step: &SagaStep) -> Vec<&SagaStep> {
+        let events = match step.target.command(schema).map(|command| &command.events) {
+            Some(CommandEvents::Single(event)) => vec![event],
+            Some(CommandEvents::Tuple(events)) => events.iter().collect(),
+            Some(CommandEvents::None) | None => vec![],
+        };
+
+        self.steps
+            .iter()
+            .filter(|next| {
+                events.iter().any(|event| match event {
+                    EventOpt::Optional(event) | EventOpt::Required(event) => {
+                        event.qualified_name == next.event
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn from_saga(
+        saga: &crate::parser::saga::Saga,
+        package: Option<&str>,
+        events: &HashMap<String, Event>,
+        aggregates: &HashMap<String, Aggregate>,
+    ) -> Result<Self, Error> {
+        if saga.steps.is_empty() {
+            return Err(Error::EmptySaga {
+                name: saga.ident.to_string(),
+                position: saga.ident.into(),
+            });
+        }
+        let steps = saga
+            .steps
+            .iter()
+            .map(|step| SagaStep::from_saga_step(step, package, events, aggregates))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Saga {
+            name: saga.ident.to_string(),
+            docs: parse_docs(&saga.docs),
+            annotations: parse_annotations(&saga.annotations)?,
+            steps,
+            position: saga.ident.into(),
+        })
+    }
+}
+
+/// Reaction of a saga to an event.
+/// - `on SentFunds -> Recipient.receive_funds`
+/// - `on ReceivedFunds -> done`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SagaStep {
+    /// Qualified name of the event, as keyed in [`Schema::events`].
+    pub event: String,
+    pub target: SagaTarget,
+    pub position: Position,
+}
+
+impl SagaStep {
+    fn from_saga_step(
+        step: &crate::parser::saga::SagaStep,
+        package: Option<&str>,
+        events: &HashMap<String, Event>,
+        aggregates: &HashMap<String, Aggregate>,
+    ) -> Result<Self, Error> {
+        let event = lookup(events, package, &step.event)?
+            .ok_or_else(|| Error::EventNotDefined {
+                name: step.event.to_string(),
+                position: step.event.into(),
+            })?
+            .qualified_name
+            .clone();
+        let target = match &step.target {
+            crate::parser::saga::SagaTarget::Command { aggregate, command } => {
+                let commands = &aggregates
+                    .get(*aggregate.fragment())
+                    .ok_or_else(|| Error::AggregateNotDefined {
+                        name: aggregate.to_string(),
+                        position: (*aggregate).into(),
+                    })?
+                    .commands;
+                if !commands.contains_key(*command.fragment()) {
+                    return Err(Error::CommandNotDefined {
+                        aggregate: aggregate.to_string(),
+                        command: command.to_string(),
+                        position: (*command).into(),
+                    });
+                }
+                SagaTarget::Command {
+                    aggregate: aggregate.to_string(),
+                    command: command.to_string(),
+                }
+            }
+            crate::parser::saga::SagaTarget::Done => SagaTarget::Done,
+        };
+
+        Ok(SagaStep {
+            event,
+            target,
+            position: step.event.into(),
+        })
+    }
+}
+
+/// What a saga does in reaction to an event.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SagaTarget {
+    /// Invokes a command of an aggregate, by their names as keyed in [`Schema::aggregates`] and
+    /// [`Aggregate::commands`].
+    Command { aggregate: String, command: String },
+    /// Completes the saga.
+    Done,
+}
+
+impl SagaTarget {
+    /// Command invoked by the target, as declared in the schema the saga belongs to.
+    pub fn command<'s>(&self, schema: &'s Schema) -> Option<&'s Command> {
+        match self {
+            SagaTarget::Command { aggregate, command } => {
+                schema.aggregates.get(aggregate)?.commands.get(command)
+            }
+            SagaTarget::Done => None,
+        }
     }
 }
 
-/// Command definition with name, params and resulting events.
+/// Command definition with name, params, resulting events and the errors it can be rejected with.
 /// - `open_account(initial_balance: Float): OpenedAccount?`
 /// - `make_transaction(amount: Float): (DepositedFunds? | WithdrewFunds)`
+/// - `withdraw_funds(amount: Float): WithdrewFunds ! (InsufficientFunds | AccountClosed)`
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Command {
     pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
     pub params: Vec<Param>,
     pub events: CommandEvents,
+    pub errors: Vec<CommandError>,
+    pub position: Position,
 }
 
 impl Command {
     fn from_command(
         command: &crate::parser::aggregate::Command,
-        custom_types: &HashMap<&str, &Vec<crate::parser::event::Field>>,
+        user_types: &UserTypes<'_, '_, '_>,
         events: &HashMap<String, Event>,
+        errors: &HashMap<String, CommandError>,
     ) -> Result<Self, Error> {
         let name = command.ident.to_string();
         let params = command
@@ -161,69 +716,135 @@ impl Command {
                     return Err(Error::DuplicateParam {
                         command: name.clone(),
                         param: param_name,
+                        position: param.ident.into(),
                     });
                 }
 
-                acc.push(Param::from_param(custom_types, param)?);
+                acc.push(Param::from_param(user_types, param)?);
 
                 Ok(acc)
             })?;
-        let events = CommandEvents::from_return_type(&command.return_type, events)?;
+        let package = user_types.package.as_deref();
+        let events = CommandEvents::from_return_type(&command.return_type, package, events)?;
+        let errors =
+            command
+                .errors
+                .iter()
+                .try_fold(Vec::<CommandError>::new(), |mut acc, error| {
+                    let command_error =
+                        lookup(errors, package, error)?.cloned().ok_or_else(|| {
+                            Error::ErrorNotDefined {
+                                name: error.to_string(),
+                                position: (*error).into(),
+                            }
+                        })?;
+                    if acc
+                        .iter()
+                        .any(|existing| existing.qualified_name == command_error.qualified_name)
+                    {
+                        return Err(Error::DuplicateCommandError {
+                            command: name.clone(),
+                            error: error.to_string(),
+                            position: (*error).into(),
+                        });
+                    }
+
+                    acc.push(command_error);
+
+                    Ok(acc)
+                })?;
 
         Ok(Command {
             name,
+            docs: parse_docs(&command.docs),
+            annotations: parse_annotations(&command.annotations)?,
             params,
             events,
+            errors,
+            position: command.ident.into(),
         })
     }
 }
 
-/// Command parameter with name and type.
+/// Command parameter with name, type and optional default value.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Param {
     pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
     pub ty: RepeatableType,
+    pub constraints: Vec<Constraint>,
+    pub default: Option<Value>,
+    pub position: Position,
 }
 
 impl Param {
     fn from_param(
-        custom_types: &HashMap<&str, &Vec<crate::parser::event::Field>>,
+        user_types: &UserTypes<'_, '_, '_>,
         param: &crate::parser::aggregate::Param,
     ) -> Result<Self, Error> {
         let name = param.ident.to_string();
-        let ty = RepeatableType::from_type(custom_types, &param.ty)?;
+        let ty = RepeatableType::from_type(user_types, &param.ty)?;
+        let default = param
+            .default
+            .as_ref()
+            .map(|literal| Value::from_literal(user_types, &name, &ty, literal))
+            .transpose()?;
+        let constraints = parse_constraints(
+            user_types,
+            &name,
+            &ty,
+            &param.constraints,
+            default.as_ref().zip(param.default.as_ref()),
+        )?;
 
-        Ok(Param { name, ty })
+        Ok(Param {
+            name,
+            docs: parse_docs(&param.docs),
+            annotations: parse_annotations(&param.annotations)?,
+            ty,
+            constraints,
+            default,
+            position: param.ident.into(),
+        })
     }
 }
 
 /// Events resulted by a command.
 /// - `Event`
-/// - `(EventOne?, EventTwo)`
+/// - `(EventOne? | EventTwo)`
+/// - `()`
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CommandEvents {
     Single(EventOpt),
     Tuple(Vec<EventOpt>),
+    /// Validation-only command, which never emits events.
+    None,
 }
 
 impl CommandEvents {
     fn from_return_type(
         return_type: &crate::parser::aggregate::ReturnType,
+        package: Option<&str>,
         events: &HashMap<String, Event>,
     ) -> Result<Self, Error> {
         match return_type {
             crate::parser::aggregate::ReturnType::Single(return_type_optional_or_required) => Ok(
                 CommandEvents::Single(EventOpt::from_return_type_optional_or_required(
                     return_type_optional_or_required,
+                    package,
                     events,
                 )?),
             ),
             crate::parser::aggregate::ReturnType::Tuple(return_events) => Ok(CommandEvents::Tuple(
                 return_events
                     .iter()
-                    .map(|event| EventOpt::from_return_type_optional_or_required(event, events))
+                    .map(|event| {
+                        EventOpt::from_return_type_optional_or_required(event, package, events)
+                    })
                     .collect::<Result<_, _>>()?,
             )),
+            crate::parser::aggregate::ReturnType::None => Ok(CommandEvents::None),
         }
     }
 }
@@ -238,22 +859,27 @@ pub enum EventOpt {
 impl EventOpt {
     fn from_return_type_optional_or_required(
         return_type_optional_or_required: &crate::parser::aggregate::ReturnTypeOptionalOrRequired,
+        package: Option<&str>,
         events: &HashMap<String, Event>,
     ) -> Result<Self, Error> {
         match return_type_optional_or_required {
             crate::parser::aggregate::ReturnTypeOptionalOrRequired::Optional(name) => {
                 Ok(EventOpt::Optional(
-                    events
-                        .get(*name)
-                        .ok_or_else(|| Error::EventNotDefined(name.to_string()))?
+                    lookup(events, package, name)?
+                        .ok_or_else(|| Error::EventNotDefined {
+                            name: name.to_string(),
+                            position: (*name).into(),
+                        })?
                         .clone(),
                 ))
             }
             crate::parser::aggregate::ReturnTypeOptionalOrRequired::Required(name) => {
                 Ok(EventOpt::Required(
-                    events
-                        .get(*name)
-                        .ok_or_else(|| Error::EventNotDefined(name.to_string()))?
+                    lookup(events, package, name)?
+                        .ok_or_else(|| Error::EventNotDefined {
+                            name: name.to_string(),
+                            position: (*name).into(),
+                        })?
                         .clone(),
                 ))
             }
@@ -270,12 +896,19 @@ impl EventOpt {
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Event {
     pub name: String,
-    pub fields: HashMap<String, RepeatableType>,
+    /// Name qualified by the package of the file declaring it, eg. `banking.accounts.Opened`.
+    pub qualified_name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub fields: HashMap<String, Field>,
+    /// File the declaration was imported from, which its positions are in.
+    pub file: Option<path::PathBuf>,
+    pub position: Position,
 }
 
 impl Event {
     fn from_event(
-        custom_types: &HashMap<&str, &Vec<crate::parser::event::Field>>,
+        user_types: &UserTypes<'_, '_, '_>,
         event: &crate::parser::event::Event,
     ) -> Result<Self, Error> {
         let name = event.ident.to_string();
@@ -286,24 +919,92 @@ impl Event {
                 if acc
                     .insert(
                         field.ident.to_string(),
-                        RepeatableType::from_type(custom_types, &field.ty)?,
+                        Field::from_field(user_types, field)?,
                     )
                     .is_some()
                 {
                     return Err(Error::DuplicateEventField {
                         event: name.clone(),
                         field: field.ident.to_string(),
+                        position: field.ident.into(),
+                    });
+                }
+
+                Ok(acc)
+            })?;
+
+        Ok(Event {
+            qualified_name: qualified_name(event.package, &name),
+            name,
+            docs: parse_docs(&event.docs),
+            annotations: parse_annotations(&event.annotations)?,
+            fields,
+            file: event.file.map(path::Path::to_path_buf),
+            position: event.ident.into(),
+        })
+    }
+}
+
+/// Domain error definition with name and fields, which commands declare they can be rejected with.
+///
+/// ```text
+/// error InsufficientFunds {
+///   balance: Float
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandError {
+    pub name: String,
+    /// Name qualified by the package of the file declaring it, eg. `banking.accounts.AccountClosed`.
+    pub qualified_name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub fields: HashMap<String, Field>,
+    /// File the declaration was imported from, which its positions are in.
+    pub file: Option<path::PathBuf>,
+    pub position: Position,
+}
+
+impl CommandError {
+    fn from_command_error(
+        user_types: &UserTypes<'_, '_, '_>,
+        error: &crate::parser::event::CommandError,
+    ) -> Result<Self, Error> {
+        let name = error.ident.to_string();
+        let fields = error
+            .fields
+            .iter()
+            .try_fold(HashMap::new(), |mut acc, field| {
+                if acc
+                    .insert(
+                        field.ident.to_string(),
+                        Field::from_field(user_types, field)?,
+                    )
+                    .is_some()
+                {
+                    return Err(Error::DuplicateErrorField {
+                        error: name.clone(),
+                        field: field.ident.to_string(),
+                        position: field.ident.into(),
                     });
                 }
 
                 Ok(acc)
             })?;
 
-        Ok(Event { name, fields })
+        Ok(CommandError {
+            qualified_name: qualified_name(error.package, &name),
+            name,
+            docs: parse_docs(&error.docs),
+            annotations: parse_annotations(&error.annotations)?,
+            fields,
+            file: error.file.map(path::Path::to_path_buf),
+            position: error.ident.into(),
+        })
     }
 }
 
-/// Custom type definition with name and fields.
+/// Custom type definition with name and fields, declared with `type` or `struct`.
 ///
 /// ```text
 /// type User {
@@ -314,15 +1015,71 @@ impl Event {
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CustomType {
     pub name: String,
-    pub fields: HashMap<String, RepeatableType>,
+    /// Name qualified by the package of the file declaring it, eg. `banking.accounts.Owner`.
+    pub qualified_name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    /// Type parameters of a generic type, referenced in its fields as [`TypeRef::Param`].
+    pub params: Vec<String>,
+    pub fields: HashMap<String, Field>,
+    /// File the declaration was imported from, which its positions are in.
+    pub file: Option<path::PathBuf>,
+    pub position: Position,
 }
 
 impl CustomType {
+    /// Custom type with its type parameters substituted by the given arguments,
+    /// eg. the fields of `Change<Money>` for `type Change<T> { old: T?, new: T }`.
+    ///
+    /// Returns `None` if the number of arguments does not match the type parameters.
+    pub fn instantiate(&self, args: &[TypeRef]) -> Option<CustomType> {
+        if args.len() != self.params.len() {
+            return None;
+        }
+
+        let args: HashMap<_, _> = self.params.iter().map(String::as_str).zip(args).collect();
+        let fields = self
+            .fields
+            .iter()
+            .map(|(name, field)| {
+                let field = Field {
+                    ty: field.ty.substitute(&args),
+                    ..field.clone()
+                };
+                (name.clone(), field)
+            })
+            .collect();
+
+        Some(CustomType {
+            params: vec![],
+            fields,
+            ..self.clone()
+        })
+    }
+
     fn from_custom_type(
-        custom_types: &HashMap<&str, &Vec<crate::parser::event::Field>>,
+        user_types: &UserTypes<'_, '_, '_>,
         ty: &crate::parser::types::CustomType,
     ) -> Result<Self, Error> {
         let name = ty.ident.to_string();
+
+        // Type parameters are in scope within the fields, shadowing any declared type.
+        let mut params = Vec::with_capacity(ty.params.len());
+        for param in &ty.params {
+            if params.contains(&param.to_string()) {
+                return Err(Error::DuplicateTypeParam {
+                    ty: name,
+                    param: param.to_string(),
+                    position: (*param).into(),
+                });
+            }
+            params.push(param.to_string());
+        }
+        let user_types = &UserTypes {
+            params: params.clone(),
+            ..user_types.clone()
+        };
+
         let fields = ty
             .fields
             .iter()
@@ -330,155 +1087,3189 @@ impl CustomType {
                 if acc
                     .insert(
                         field.ident.to_string(),
-                        RepeatableType::from_type(custom_types, &field.ty)?,
+                        Field::from_field(user_types, field)?,
                     )
                     .is_some()
                 {
                     return Err(Error::DuplicateCustomTypeField {
                         ty: name.clone(),
                         field: field.ident.to_string(),
+                        position: field.ident.into(),
                     });
                 }
 
                 Ok(acc)
             })?;
 
-        Ok(CustomType { name, fields })
+        Ok(CustomType {
+            qualified_name: qualified_name(ty.package, &name),
+            name,
+            docs: parse_docs(&ty.docs),
+            annotations: parse_annotations(&ty.annotations)?,
+            params,
+            fields,
+            file: ty.file.map(path::Path::to_path_buf),
+            position: ty.ident.into(),
+        })
     }
 }
 
-/// A type which can be a single type or array type.
-/// - `String`
-/// - `[String]?`
-/// - `[String]`
+/// Event or custom type field with name, type and optional default value.
+///
+/// Adding a field with a default allows an event to evolve without breaking the replay of events
+/// stored before the field existed.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub enum RepeatableType {
-    Single(TypeOpt),
-    OptionalArray(TypeOpt),
-    RequiredArray(TypeOpt),
+pub struct Field {
+    pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub ty: RepeatableType,
+    pub constraints: Vec<Constraint>,
+    pub default: Option<Value>,
+    pub position: Position,
 }
 
-impl RepeatableType {
-    fn from_type(
-        custom_types: &HashMap<&str, &Vec<crate::parser::event::Field>>,
-        ty: &crate::parser::types::Type,
+impl Field {
+    fn from_field(
+        user_types: &UserTypes<'_, '_, '_>,
+        field: &crate::parser::event::Field,
     ) -> Result<Self, Error> {
-        match ty {
-            crate::parser::types::Type::Single(type_opt) => Ok(RepeatableType::Single(
-                TypeOpt::from_optional_or_required_type(custom_types, type_opt)?,
-            )),
-            crate::parser::types::Type::Array {
-                inner,
-                optional: true,
-            } => Ok(RepeatableType::OptionalArray(
-                TypeOpt::from_optional_or_required_type(custom_types, inner)?,
-            )),
-            crate::parser::types::Type::Array {
-                inner,
-                optional: false,
-            } => Ok(RepeatableType::RequiredArray(
-                TypeOpt::from_optional_or_required_type(custom_types, inner)?,
-            )),
-        }
+        let name = field.ident.to_string();
+        let ty = RepeatableType::from_type(user_types, &field.ty)?;
+        let default = field
+            .default
+            .as_ref()
+            .map(|literal| Value::from_literal(user_types, &name, &ty, literal))
+            .transpose()?;
+        let constraints = parse_constraints(
+            user_types,
+            &name,
+            &ty,
+            &field.constraints,
+            default.as_ref().zip(field.default.as_ref()),
+        )?;
+
+        Ok(Field {
+            name,
+            docs: parse_docs(&field.docs),
+            annotations: parse_annotations(&field.annotations)?,
+            ty,
+            constraints,
+            default,
+            position: field.ident.into(),
+        })
     }
 }
 
-/// An optional or required type.
-/// - `String?`
-/// - `String`
+/// Annotation attached to an item, with its arguments.
+///
+/// ```text
+/// @deprecated("use close_account")
+/// @since("0.3.0")
+/// ```
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub enum TypeOpt {
-    Optional(TypeRef),
-    Required(TypeRef),
+pub struct Annotation {
+    pub name: String,
+    pub args: Vec<Value>,
+    pub position: Position,
 }
 
-impl TypeOpt {
-    fn from_optional_or_required_type(
-        custom_types: &HashMap<&str, &Vec<crate::parser::event::Field>>,
-        ty: &crate::parser::types::OptionalOrRequiredType,
-    ) -> Result<Self, Error> {
-        match ty {
-            crate::parser::types::OptionalOrRequiredType::Optional(type_ref) => Ok(
-                TypeOpt::Optional(TypeRef::from_scalar_or_user_type(custom_types, type_ref)?),
-            ),
-            crate::parser::types::OptionalOrRequiredType::Required(type_ref) => Ok(
-                TypeOpt::Required(TypeRef::from_scalar_or_user_type(custom_types, type_ref)?),
+impl Annotation {
+    fn from_annotation(annotation: &crate::parser::annotation::Annotation) -> Result<Self, Error> {
+        let name = annotation.ident.to_string();
+        let args = annotation
+            .args
+            .iter()
+            .map(|arg| {
+                Value::from_untyped_literal(arg).ok_or_else(|| Error::InvalidAnnotationArgument {
+                    name: name.clone(),
+                    position: arg.span().into(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Annotation {
+            name,
+            args,
+            position: annotation.ident.into(),
+        })
+    }
+}
+
+/// Constraint on the value of a field or param, checked against its declared type.
+///
+/// ```text
+/// amount: Float @min(0)
+/// name: String @length(1, 100)
+/// code: String @pattern("^[A-Z]{3}$")
+/// items: [Item] @max_items(50)
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Constraint {
+    /// Inclusive minimum of a numeric scalar.
+    Min(Value),
+    /// Inclusive maximum of a numeric scalar.
+    Max(Value),
+    /// Inclusive bounds on the number of characters in a string.
+    Length { min: u64, max: u64 },
+    /// Regular expression a string must match.
+    Pattern(Pattern),
+    /// Minimum number of items in an array or map.
+    MinItems(u64),
+    /// Maximum number of items in an array or map.
+    MaxItems(u64),
+}
+
+impl Constraint {
+    /// Whether the value satisfies the constraint.
+    ///
+    /// Values of a kind the constraint does not apply to never satisfy it.
+    pub fn check(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Constraint::Min(min), value) => value.compare(min).is_some_and(Ordering::is_ge),
+            (Constraint::Max(max), value) => value.compare(max).is_some_and(Ordering::is_le),
+            (Constraint::Length { min, max }, Value::String(value)) => {
+                (*min..=*max).contains(&(value.chars().count() as u64))
+            }
+            (Constraint::Pattern(pattern), Value::String(value)) => pattern.is_match(value),
+            (Constraint::MinItems(min), Value::Array(items)) => items.len() as u64 >= *min,
+            (Constraint::MaxItems(max), Value::Array(items)) => items.len() as u64 <= *max,
+            _ => false,
+        }
+    }
+
+    fn from_annotation(
+        user_types: &UserTypes<'_, '_, '_>,
+        ty: &RepeatableType,
+        constraint: &crate::parser::annotation::Annotation,
+    ) -> Result<Self, Error> {
+        let name = *constraint.ident.fragment();
+        let invalid_argument = |position: crate::parser::Span| Error::InvalidConstraintArgument {
+            name: name.to_string(),
+            position: position.into(),
+        };
+        let count = |arg: &crate::parser::literal::Literal| match arg {
+            crate::parser::literal::Literal::Int(value) => value
+                .fragment()
+                .parse()
+                .map_err(|_| invalid_argument(*value)),
+            arg => Err(invalid_argument(arg.span())),
+        };
+
+        let resolved = ty.resolve_aliases(user_types)?;
+        let scalar = match &resolved {
+            RepeatableType::Single(TypeOpt::Optional(type_ref) | TypeOpt::Required(type_ref)) => {
+                type_ref.scalar(user_types)
+            }
+            _ => None,
+        };
+        let applies = match name {
+            "min" | "max" => matches!(
+                scalar,
+                Some(
+                    Scalar::Int
+                        | Scalar::UInt
+                        | Scalar::Long
+                        | Scalar::ULong
+                        | Scalar::Float
+                        | Scalar::Double
+                        | Scalar::Decimal
+                )
             ),
+            "length" | "pattern" => scalar == Some(Scalar::String),
+            "min_items" | "max_items" => !matches!(resolved, RepeatableType::Single(_)),
+            _ => {
+                return Err(Error::UnknownConstraint {
+                    name: name.to_string(),
+                    position: constraint.ident.into(),
+                })
+            }
+        };
+        if !applies {
+            return Err(Error::InvalidConstraint {
+                name: name.to_string(),
+                ty: ty.to_string(),
+                position: constraint.ident.into(),
+            });
+        }
+
+        match (name, constraint.args.as_slice()) {
+            ("min" | "max", [arg]) => {
+                let value =
+                    Value::from_scalar(scalar, arg).ok_or_else(|| invalid_argument(arg.span()))?;
+                Ok(if name == "min" {
+                    Constraint::Min(value)
+                } else {
+                    Constraint::Max(value)
+                })
+            }
+            ("length", [min_arg, max_arg]) => {
+                let (min, max) = (count(min_arg)?, count(max_arg)?);
+                if min > max {
+                    return Err(invalid_argument(max_arg.span()));
+                }
+                Ok(Constraint::Length { min, max })
+            }
+            ("pattern", [arg]) => match arg {
+                crate::parser::literal::Literal::String(value) => {
+                    let pattern = Pattern::new(&parse_string_literal(value.fragment()))
+                        .map_err(|_| invalid_argument(*value))?;
+                    Ok(Constraint::Pattern(pattern))
+                }
+                arg => Err(invalid_argument(arg.span())),
+            },
+            ("min_items", [arg]) => Ok(Constraint::MinItems(count(arg)?)),
+            ("max_items", [arg]) => Ok(Constraint::MaxItems(count(arg)?)),
+            _ => Err(invalid_argument(constraint.ident)),
         }
     }
 }
 
-/// A type reference of either scalar or custom type.
-/// - `Int`
-/// - `MyType`
+/// Regular expression of a `@pattern` constraint, compiled once when the schema is validated.
+///
+/// Serialized as the pattern string.
+#[derive(Clone, Debug)]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Pattern)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Whether the string contains a match of the pattern.
+    pub fn is_match(&self, value: &str) -> bool {
+        self.0.is_match(value)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Pattern {}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Pattern::new(&pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Default value of a field or param, type checked against its declared type, or an annotation
+/// argument.
+/// - `"AUD"`
+/// - `3`
+/// - `12.50`
+/// - `true`
+/// - `[]`
+/// - `Aud`
+///
+/// Scalars such as `Timestamp` and `Uuid` take a string in their JSON representation.
+/// A custom scalar takes a literal of its base scalar, or any scalar literal if it has none.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub enum TypeRef {
-    Scalar(Scalar),
-    Custom(CustomType),
+pub enum Value {
+    String(String),
+    Int(i128),
+    /// Decimal number as written in the schema, so that no precision is lost.
+    Float(String),
+    Bool(bool),
+    Array(Vec<Value>),
+    EnumVariant(String),
 }
 
-impl TypeRef {
-    fn from_scalar_or_user_type(
-        custom_types: &HashMap<&str, &Vec<crate::parser::event::Field>>,
-        type_ref: &crate::parser::types::ScalarOrUserType,
-    ) -> Result<Self, Error> {
-        match type_ref {
-            crate::parser::types::ScalarOrUserType::Scalar(scalar) => {
-                Ok(TypeRef::Scalar((*scalar).into()))
+impl Value {
+    /// Value of a literal without a declared type, where a bare identifier is an enum variant.
+    fn from_untyped_literal(literal: &crate::parser::literal::Literal) -> Option<Self> {
+        match literal {
+            crate::parser::literal::Literal::String(value) => {
+                Some(Value::String(parse_string_literal(value.fragment())))
             }
-            crate::parser::types::ScalarOrUserType::UserDefined(custom) => {
-                let fields = custom_types
-                    .get(custom)
-                    .ok_or_else(|| Error::TypeNotDefined(custom.to_string()))?
+            crate::parser::literal::Literal::Int(value) => {
+                value.fragment().parse().ok().map(Value::Int)
+            }
+            crate::parser::literal::Literal::Float(value) => Some(Value::Float(value.to_string())),
+            crate::parser::literal::Literal::Bool(value) => {
+                Some(Value::Bool(*value.fragment() == "true"))
+            }
+            crate::parser::literal::Literal::Array(_, items) => items
+                .iter()
+                .map(Value::from_untyped_literal)
+                .collect::<Option<_>>()
+                .map(Value::Array),
+            crate::parser::literal::Literal::EnumVariant(value) => {
+                Some(Value::EnumVariant(value.to_string()))
+            }
+        }
+    }
+
+    /// Ordering of two numbers, which is exact when both are written in plain decimal notation,
+    /// such as `-12.50`, and otherwise compares them as `f64`.
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (a, b) => {
+                let (a, b) = (a.as_number()?, b.as_number()?);
+                compare_decimals(&a, &b)
+                    .or_else(|| a.parse::<f64>().ok()?.partial_cmp(&b.parse().ok()?))
+            }
+        }
+    }
+
+    fn as_number(&self) -> Option<String> {
+        match self {
+            Value::Int(value) => Some(value.to_string()),
+            Value::Float(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn from_literal(
+        user_types: &UserTypes<'_, '_, '_>,
+        name: &str,
+        ty: &RepeatableType,
+        literal: &crate::parser::literal::Literal,
+    ) -> Result<Self, Error> {
+        match (ty, literal) {
+            (
+                RepeatableType::Single(TypeOpt::Optional(type_ref) | TypeOpt::Required(type_ref)),
+                _,
+            ) => Value::from_type_ref(user_types, name, type_ref, literal),
+            (
+                RepeatableType::OptionalArray(inner) | RepeatableType::RequiredArray(inner),
+                crate::parser::literal::Literal::Array(_, items),
+            ) => Ok(Value::Array(
+                items
                     .iter()
-                    .try_fold(HashMap::new(), |mut acc, field| {
-                        if acc
-                            .insert(
-                                field.ident.to_string(),
-                                RepeatableType::from_type(custom_types, &field.ty)?,
-                            )
-                            .is_some()
-                        {
-                            return Err(Error::DuplicateTypeField {
-                                ty: custom.to_string(),
-                                field: field.ident.to_string(),
-                            });
-                        }
+                    .map(|item| Value::from_literal(user_types, name, inner, item))
+                    .collect::<Result<_, _>>()?,
+            )),
+            _ => Err(Error::InvalidDefault {
+                name: name.to_string(),
+                ty: ty.to_string(),
+                position: literal.span().into(),
+            }),
+        }
+    }
 
-                        Ok(acc)
-                    })?;
+    fn from_type_ref(
+        user_types: &UserTypes<'_, '_, '_>,
+        name: &str,
+        type_ref: &TypeRef,
+        literal: &crate::parser::literal::Literal,
+    ) -> Result<Self, Error> {
+        let user_type = |name: &str| user_types.get(name);
+        let value = match type_ref {
+            TypeRef::Scalar(scalar) => Value::from_scalar(Some(*scalar), literal),
+            TypeRef::CustomScalar(ty) => match user_type(ty) {
+                Some(UserType::Scalar(ty)) => {
+                    Value::from_scalar(ty.base.map(Scalar::from), literal)
+                }
+                _ => None,
+            },
+            TypeRef::Enum(ty) => match (user_type(ty), literal) {
+                (
+                    Some(UserType::Enum(ty)),
+                    crate::parser::literal::Literal::EnumVariant(variant),
+                ) if ty
+                    .variants
+                    .iter()
+                    .any(|ty| ty.ident.fragment() == variant.fragment()) =>
+                {
+                    Some(Value::EnumVariant(variant.to_string()))
+                }
+                _ => None,
+            },
+            TypeRef::Alias(_) => {
+                match RepeatableType::Single(TypeOpt::Required(type_ref.clone()))
+                    .resolve_aliases(user_types)?
+                {
+                    RepeatableType::Single(
+                        TypeOpt::Optional(TypeRef::Alias(_)) | TypeOpt::Required(TypeRef::Alias(_)),
+                    ) => None,
+                    ty => return Value::from_literal(user_types, name, &ty, literal),
+                }
+            }
+            TypeRef::Custom(_)
+            | TypeRef::Union(_)
+            | TypeRef::Generic { .. }
+            | TypeRef::Param(_) => None,
+        };
 
-                Ok(TypeRef::Custom(CustomType {
-                    name: custom.to_string(),
-                    fields,
-                }))
+        value.ok_or_else(|| Error::InvalidDefault {
+            name: name.to_string(),
+            ty: type_ref.to_string(),
+            position: literal.span().into(),
+        })
+    }
+
+    /// Scalar value, where a custom scalar without a base scalar accepts any scalar literal.
+    fn from_scalar(
+        scalar: Option<Scalar>,
+        literal: &crate::parser::literal::Literal,
+    ) -> Option<Self> {
+        match (scalar, literal) {
+            (
+                None
+                | Some(
+                    Scalar::String
+                    | Scalar::Bytes
+                    | Scalar::Timestamp
+                    | Scalar::Date
+                    | Scalar::Duration
+                    | Scalar::Uuid,
+                ),
+                crate::parser::literal::Literal::String(value),
+            ) => Some(Value::String(parse_string_literal(value.fragment()))),
+            (scalar, crate::parser::literal::Literal::Int(value)) => {
+                let value: i128 = value.fragment().parse().ok()?;
+                let in_range = match scalar {
+                    Some(Scalar::Int) => i32::try_from(value).is_ok(),
+                    Some(Scalar::UInt) => u32::try_from(value).is_ok(),
+                    Some(Scalar::Long) => i64::try_from(value).is_ok(),
+                    Some(Scalar::ULong) => u64::try_from(value).is_ok(),
+                    None | Some(Scalar::Float | Scalar::Double | Scalar::Decimal) => true,
+                    Some(_) => false,
+                };
+                in_range.then_some(Value::Int(value))
             }
+            (
+                None | Some(Scalar::Float | Scalar::Double | Scalar::Decimal),
+                crate::parser::literal::Literal::Float(value),
+            ) => Some(Value::Float(value.to_string())),
+            (None | Some(Scalar::Bool), crate::parser::literal::Literal::Bool(value)) => {
+                Some(Value::Bool(*value.fragment() == "true"))
+            }
+            _ => None,
         }
     }
 }
 
-/// An in-built scalar type.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub enum Scalar {
-    String,
-    Int,
-    Long,
-    Float,
-    Double,
-    Bool,
-    Bytes,
+/// Tagged union definition with name and variants, each carrying their own fields.
+///
+/// ```text
+/// type PaymentMethod = Card { last4: String } | BankTransfer { bsb: String, account: String }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Union {
+    pub name: String,
+    /// Name qualified by the package of the file declaring it, eg. `banking.accounts.PaymentMethod`.
+    pub qualified_name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub variants: Vec<UnionVariant>,
+    /// File the declaration was imported from, which its positions are in.
+    pub file: Option<path::PathBuf>,
+    pub position: Position,
 }
 
-impl From<crate::parser::types::Scalar> for Scalar {
-    fn from(scalar: crate::parser::types::Scalar) -> Self {
-        match scalar {
-            crate::parser::types::Scalar::String => Scalar::String,
-            crate::parser::types::Scalar::Int => Scalar::Int,
-            crate::parser::types::Scalar::Long => Scalar::Long,
-            crate::parser::types::Scalar::Float => Scalar::Float,
-            crate::parser::types::Scalar::Double => Scalar::Double,
-            crate::parser::types::Scalar::Bool => Scalar::Bool,
-            crate::parser::types::Scalar::Bytes => Scalar::Bytes,
+impl Union {
+    fn from_union(
+        user_types: &UserTypes<'_, '_, '_>,
+        ty: &crate::parser::types::Union,
+    ) -> Result<Self, Error> {
+        let name = ty.ident.to_string();
+        let variants =
+            ty.variants
+                .iter()
+                .try_fold(Vec::<UnionVariant>::new(), |mut acc, variant| {
+                    let variant_name = variant.ident.to_string();
+                    if acc.iter().any(|variant| variant.name == variant_name) {
+                        return Err(Error::DuplicateUnionVariant {
+                            ty: name.clone(),
+                            variant: variant_name,
+                            position: variant.ident.into(),
+                        });
+                    }
+
+                    acc.push(UnionVariant::from_union_variant(
+                        user_types, &name, variant,
+                    )?);
+
+                    Ok(acc)
+                })?;
+
+        Ok(Union {
+            qualified_name: qualified_name(ty.package, &name),
+            name,
+            docs: parse_docs(&ty.docs),
+            annotations: parse_annotations(&ty.annotations)?,
+            variants,
+            file: ty.file.map(path::Path::to_path_buf),
+            position: ty.ident.into(),
+        })
+    }
+}
+
+/// Union variant with name and fields.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnionVariant {
+    pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub fields: HashMap<String, Field>,
+    pub position: Position,
+}
+
+impl UnionVariant {
+    fn from_union_variant(
+        user_types: &UserTypes<'_, '_, '_>,
+        ty: &str,
+        variant: &crate::parser::types::UnionVariant,
+    ) -> Result<Self, Error> {
+        let name = variant.ident.to_string();
+        let fields = variant
+            .fields
+            .iter()
+            .try_fold(HashMap::new(), |mut acc, field| {
+                if acc
+                    .insert(
+                        field.ident.to_string(),
+                        Field::from_field(user_types, field)?,
+                    )
+                    .is_some()
+                {
+                    return Err(Error::DuplicateUnionVariantField {
+                        ty: ty.to_string(),
+                        variant: name.clone(),
+                        field: field.ident.to_string(),
+                        position: field.ident.into(),
+                    });
+                }
+
+                Ok(acc)
+            })?;
+
+        Ok(UnionVariant {
+            name,
+            docs: parse_docs(&variant.docs),
+            annotations: parse_annotations(&variant.annotations)?,
+            fields,
+            position: variant.ident.into(),
+        })
+    }
+}
+
+/// Type alias definition with name and aliased type.
+///
+/// ```text
+/// type Money = Float;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alias {
+    pub name: String,
+    /// Name qualified by the package of the file declaring it, eg. `banking.accounts.Tags`.
+    pub qualified_name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub ty: RepeatableType,
+    /// File the declaration was imported from, which its positions are in.
+    pub file: Option<path::PathBuf>,
+    pub position: Position,
+}
+
+impl Alias {
+    fn from_alias(
+        user_types: &UserTypes<'_, '_, '_>,
+        ty: &crate::parser::types::Alias,
+    ) -> Result<Self, Error> {
+        Ok(Alias {
+            name: ty.ident.to_string(),
+            qualified_name: qualified_name(ty.package, ty.ident.fragment()),
+            docs: parse_docs(&ty.docs),
+            annotations: parse_annotations(&ty.annotations)?,
+            ty: RepeatableType::from_type(user_types, &ty.ty)?,
+            file: ty.file.map(path::Path::to_path_buf),
+            position: ty.ident.into(),
+        })
+    }
+}
+
+/// Enum definition with name and variants.
+///
+/// ```text
+/// enum Currency {
+///   Aud,
+///   Usd,
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Enum {
+    pub name: String,
+    /// Name qualified by the package of the file declaring it, eg. `banking.accounts.Currency`.
+    pub qualified_name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub variants: Vec<EnumVariant>,
+    /// File the declaration was imported from, which its positions are in.
+    pub file: Option<path::PathBuf>,
+    pub position: Position,
+}
+
+impl Enum {
+    fn from_enum(ty: &crate::parser::types::Enum) -> Result<Self, Error> {
+        let name = ty.ident.to_string();
+        let variants =
+            ty.variants
+                .iter()
+                .try_fold(Vec::<EnumVariant>::new(), |mut acc, variant| {
+                    let variant_name = variant.ident.to_string();
+                    if acc.iter().any(|variant| variant.name == variant_name) {
+                        return Err(Error::DuplicateEnumVariant {
+                            ty: name.clone(),
+                            variant: variant_name,
+                            position: variant.ident.into(),
+                        });
+                    }
+
+                    acc.push(EnumVariant {
+                        name: variant_name,
+                        docs: parse_docs(&variant.docs),
+                        annotations: parse_annotations(&variant.annotations)?,
+                        position: variant.ident.into(),
+                    });
+
+                    Ok(acc)
+                })?;
+
+        Ok(Enum {
+            qualified_name: qualified_name(ty.package, &name),
+            name,
+            docs: parse_docs(&ty.docs),
+            annotations: parse_annotations(&ty.annotations)?,
+            variants,
+            file: ty.file.map(path::Path::to_path_buf),
+            position: ty.ident.into(),
+        })
+    }
+}
+
+/// Enum variant with name.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnumVariant {
+    pub name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub position: Position,
+}
+
+/// Custom scalar definition with name, optionally backed by a built-in scalar.
+///
+/// ```text
+/// scalar Email
+/// scalar Iban : String
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomScalar {
+    pub name: String,
+    /// Name qualified by the package of the file declaring it, eg. `banking.accounts.Iban`.
+    pub qualified_name: String,
+    pub docs: Option<String>,
+    pub annotations: Vec<Annotation>,
+    pub base: Option<Scalar>,
+    /// File the declaration was imported from, which its positions are in.
+    pub file: Option<path::PathBuf>,
+    pub position: Position,
+}
+
+impl CustomScalar {
+    fn from_custom_scalar(ty: &crate::parser::types::CustomScalar) -> Result<Self, Error> {
+        Ok(CustomScalar {
+            name: ty.ident.to_string(),
+            qualified_name: qualified_name(ty.package, ty.ident.fragment()),
+            docs: parse_docs(&ty.docs),
+            annotations: parse_annotations(&ty.annotations)?,
+            base: ty.base.map(Scalar::from),
+            file: ty.file.map(path::Path::to_path_buf),
+            position: ty.ident.into(),
+        })
+    }
+}
+
+/// A type which can be a single type, array type or map type.
+/// - `String`
+/// - `[String]?`
+/// - `[String]`
+/// - `[[String?]]?`
+/// - `{String: Int?}?`
+/// - `{String: Int}`
+/// - `{String: [Int]}`
+///
+/// Array items and map values can be of any type, each with their own optionality.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RepeatableType {
+    Single(TypeOpt),
+    OptionalArray(Box<RepeatableType>),
+    RequiredArray(Box<RepeatableType>),
+    OptionalMap {
+        key: TypeRef,
+        value: Box<RepeatableType>,
+    },
+    RequiredMap {
+        key: TypeRef,
+        value: Box<RepeatableType>,
+    },
+}
+
+impl RepeatableType {
+    fn from_type(
+        user_types: &UserTypes<'_, '_, '_>,
+        ty: &crate::parser::types::Type,
+    ) -> Result<Self, Error> {
+        match ty {
+            crate::parser::types::Type::Single(type_opt) => Ok(RepeatableType::Single(
+                TypeOpt::from_optional_or_required_type(user_types, type_opt)?,
+            )),
+            crate::parser::types::Type::Array {
+                inner,
+                optional: true,
+            } => Ok(RepeatableType::OptionalArray(Box::new(
+                RepeatableType::from_type(user_types, inner)?,
+            ))),
+            crate::parser::types::Type::Array {
+                inner,
+                optional: false,
+            } => Ok(RepeatableType::RequiredArray(Box::new(
+                RepeatableType::from_type(user_types, inner)?,
+            ))),
+            crate::parser::types::Type::Map {
+                key,
+                value,
+                optional: true,
+            } => Ok(RepeatableType::OptionalMap {
+                key: TypeRef::from_map_key(user_types, key)?,
+                value: Box::new(RepeatableType::from_type(user_types, value)?),
+            }),
+            crate::parser::types::Type::Map {
+                key,
+                value,
+                optional: false,
+            } => Ok(RepeatableType::RequiredMap {
+                key: TypeRef::from_map_key(user_types, key)?,
+                value: Box::new(RepeatableType::from_type(user_types, value)?),
+            }),
+        }
+    }
+
+    /// Type with aliases followed to the type they refer to.
+    ///
+    /// Aliases forming a cycle through optional types are left unresolved.
+    fn resolve_aliases(&self, user_types: &UserTypes<'_, '_, '_>) -> Result<RepeatableType, Error> {
+        let mut ty = self.clone();
+        let mut visited = HashSet::new();
+        while let RepeatableType::Single(
+            TypeOpt::Optional(TypeRef::Alias(name)) | TypeOpt::Required(TypeRef::Alias(name)),
+        ) = &ty
+        {
+            match user_types.get(name) {
+                Some(UserType::Alias(alias)) if visited.insert(name.clone()) => {
+                    ty = RepeatableType::from_type(user_types, &alias.ty)?;
+                }
+                _ => break,
+            }
+        }
+
+        Ok(ty)
+    }
+
+    /// Type with type parameters replaced by their arguments.
+    fn substitute(&self, args: &HashMap<&str, &TypeRef>) -> RepeatableType {
+        match self {
+            RepeatableType::Single(ty) => RepeatableType::Single(ty.substitute(args)),
+            RepeatableType::OptionalArray(ty) => {
+                RepeatableType::OptionalArray(Box::new(ty.substitute(args)))
+            }
+            RepeatableType::RequiredArray(ty) => {
+                RepeatableType::RequiredArray(Box::new(ty.substitute(args)))
+            }
+            RepeatableType::OptionalMap { key, value } => RepeatableType::OptionalMap {
+                key: key.substitute(args),
+                value: Box::new(value.substitute(args)),
+            },
+            RepeatableType::RequiredMap { key, value } => RepeatableType::RequiredMap {
+                key: key.substitute(args),
+                value: Box::new(value.substitute(args)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for RepeatableType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepeatableType::Single(ty) => write!(f, "{ty}"),
+            RepeatableType::OptionalArray(ty) => write!(f, "[{ty}]?"),
+            RepeatableType::RequiredArray(ty) => write!(f, "[{ty}]"),
+            RepeatableType::OptionalMap { key, value } => write!(f, "{{{key}: {value}}}?"),
+            RepeatableType::RequiredMap { key, value } => write!(f, "{{{key}: {value}}}"),
         }
     }
 }
+
+/// An optional or required type.
+/// - `String?`
+/// - `String`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TypeOpt {
+    Optional(TypeRef),
+    Required(TypeRef),
+}
+
+impl TypeOpt {
+    fn from_optional_or_required_type(
+        user_types: &UserTypes<'_, '_, '_>,
+        ty: &crate::parser::types::OptionalOrRequiredType,
+    ) -> Result<Self, Error> {
+        match ty {
+            crate::parser::types::OptionalOrRequiredType::Optional(type_ref) => Ok(
+                TypeOpt::Optional(TypeRef::from_scalar_or_user_type(user_types, type_ref)?),
+            ),
+            crate::parser::types::OptionalOrRequiredType::Required(type_ref) => Ok(
+                TypeOpt::Required(TypeRef::from_scalar_or_user_type(user_types, type_ref)?),
+            ),
+        }
+    }
+
+    fn substitute(&self, args: &HashMap<&str, &TypeRef>) -> TypeOpt {
+        match self {
+            TypeOpt::Optional(ty) => TypeOpt::Optional(ty.substitute(args)),
+            TypeOpt::Required(ty) => TypeOpt::Required(ty.substitute(args)),
+        }
+    }
+}
+
+impl fmt::Display for TypeOpt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeOpt::Optional(ty) => write!(f, "{ty}?"),
+            TypeOpt::Required(ty) => write!(f, "{ty}"),
+        }
+    }
+}
+
+/// A type reference of either scalar, custom type, union, alias, enum or custom scalar.
+/// - `Int`
+/// - `MyType`
+/// - `MyGeneric<Int>`
+/// - `MyUnion`
+/// - `MyAlias`
+/// - `MyEnum`
+/// - `MyScalar`
+///
+/// User defined types are referenced by qualified name, such as `common.Money`, and can be looked
+/// up in [`Schema::types`], [`Schema::unions`], [`Schema::aliases`], [`Schema::enums`] and
+/// [`Schema::scalars`] respectively.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TypeRef {
+    Scalar(Scalar),
+    Custom(String),
+    /// Generic custom type applied to type arguments, see [`CustomType::instantiate`].
+    Generic {
+        name: String,
+        args: Vec<TypeRef>,
+    },
+    /// Type parameter, within the fields of the generic custom type declaring it.
+    Param(String),
+    Union(String),
+    Alias(String),
+    Enum(String),
+    CustomScalar(String),
+}
+
+impl TypeRef {
+    /// Scalar the type is, or is based on for a custom scalar.
+    fn scalar(&self, user_types: &UserTypes<'_, '_, '_>) -> Option<Scalar> {
+        match self {
+            TypeRef::Scalar(scalar) => Some(*scalar),
+            TypeRef::CustomScalar(name) => match user_types.get(name) {
+                Some(UserType::Scalar(ty)) => ty.base.map(Scalar::from),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Map keys must be a scalar, custom scalar or an enum, or an alias of one.
+    fn from_map_key(
+        user_types: &UserTypes<'_, '_, '_>,
+        key: &crate::parser::types::ScalarOrUserType,
+    ) -> Result<Self, Error> {
+        let type_ref = TypeRef::from_scalar_or_user_type(user_types, key)?;
+        let name = match key {
+            crate::parser::types::ScalarOrUserType::Scalar(_) => return Ok(type_ref),
+            crate::parser::types::ScalarOrUserType::UserDefined(name)
+            | crate::parser::types::ScalarOrUserType::Generic { ident: name, .. } => name,
+        };
+        match RepeatableType::Single(TypeOpt::Required(type_ref.clone()))
+            .resolve_aliases(user_types)?
+        {
+            RepeatableType::Single(TypeOpt::Required(
+                TypeRef::Scalar(_) | TypeRef::Enum(_) | TypeRef::CustomScalar(_),
+            )) => Ok(type_ref),
+            _ => Err(Error::InvalidMapKey {
+                name: name.to_string(),
+                position: (*name).into(),
+            }),
+        }
+    }
+
+    /// Type with type parameters replaced by their arguments.
+    fn substitute(&self, args: &HashMap<&str, &TypeRef>) -> TypeRef {
+        match self {
+            TypeRef::Param(name) => args
+                .get(name.as_str())
+                .map_or_else(|| self.clone(), |arg| (*arg).clone()),
+            TypeRef::Generic { name, args: inner } => TypeRef::Generic {
+                name: name.clone(),
+                args: inner.iter().map(|arg| arg.substitute(args)).collect(),
+            },
+            _ => self.clone(),
+        }
+    }
+
+    fn from_scalar_or_user_type(
+        user_types: &UserTypes<'_, '_, '_>,
+        type_ref: &crate::parser::types::ScalarOrUserType,
+    ) -> Result<Self, Error> {
+        match type_ref {
+            crate::parser::types::ScalarOrUserType::Scalar(scalar) => {
+                Ok(TypeRef::Scalar((*scalar).into()))
+            }
+            crate::parser::types::ScalarOrUserType::UserDefined(custom) => {
+                TypeRef::from_user_type(user_types, custom, &[])
+            }
+            crate::parser::types::ScalarOrUserType::Generic { ident, args } => {
+                TypeRef::from_user_type(user_types, ident, args)
+            }
+        }
+    }
+
+    /// User defined type, which must be given as many type arguments as it has type parameters.
+    fn from_user_type(
+        user_types: &UserTypes<'_, '_, '_>,
+        custom: &crate::parser::Span,
+        args: &[crate::parser::types::Type],
+    ) -> Result<Self, Error> {
+        let Some((name, user_type)) = user_types.resolve(custom)? else {
+            return Err(Error::TypeNotDefined {
+                name: custom.to_string(),
+                position: (*custom).into(),
+            });
+        };
+
+        let expected = match user_type {
+            UserType::Custom(ty) => ty.params.len(),
+            _ => 0,
+        };
+        if args.len() != expected {
+            return Err(Error::InvalidTypeArguments {
+                name: custom.to_string(),
+                expected,
+                found: args.len(),
+                position: (*custom).into(),
+            });
+        }
+
+        match user_type {
+            UserType::Custom(_) if !args.is_empty() => Ok(TypeRef::Generic {
+                name,
+                args: args
+                    .iter()
+                    .map(|arg| match arg {
+                        crate::parser::types::Type::Single(
+                            crate::parser::types::OptionalOrRequiredType::Required(arg),
+                        ) => TypeRef::from_scalar_or_user_type(user_types, arg),
+                        _ => Err(Error::UnsupportedTypeArgument {
+                            name: custom.to_string(),
+                            argument: RepeatableType::from_type(user_types, arg)?.to_string(),
+                            position: (*custom).into(),
+                        }),
+                    })
+                    .collect::<Result<_, _>>()?,
+            }),
+            UserType::Custom(_) => Ok(TypeRef::Custom(name)),
+            UserType::Param => Ok(TypeRef::Param(name)),
+            UserType::Union(_) => Ok(TypeRef::Union(name)),
+            UserType::Alias(_) => Ok(TypeRef::Alias(name)),
+            UserType::Enum(_) => Ok(TypeRef::Enum(name)),
+            UserType::Scalar(_) => Ok(TypeRef::CustomScalar(name)),
+        }
+    }
+}
+
+impl fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeRef::Scalar(scalar) => write!(f, "{scalar}"),
+            TypeRef::Generic { name, args } => {
+                write!(f, "{name}<")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                write!(f, ">")
+            }
+            TypeRef::Custom(name)
+            | TypeRef::Param(name)
+            | TypeRef::Union(name)
+            | TypeRef::Alias(name)
+            | TypeRef::Enum(name)
+            | TypeRef::CustomScalar(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// An in-built scalar type.
+///
+/// Each scalar documents how it is represented when encoded as JSON.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scalar {
+    /// UTF-8 text, as a JSON string.
+    String,
+    /// 32-bit signed integer, as a JSON number.
+    Int,
+    /// 32-bit unsigned integer, as a JSON number.
+    UInt,
+    /// 64-bit signed integer, as a JSON number.
+    Long,
+    /// 64-bit unsigned integer, as a JSON number.
+    ULong,
+    /// 32-bit float, as a JSON number.
+    Float,
+    /// 64-bit float, as a JSON number.
+    Double,
+    /// Arbitrary-precision decimal, as a JSON string such as `"1024.50"`.
+    ///
+    /// Encoded as a string so no precision is lost to floating point numbers.
+    Decimal,
+    /// Boolean, as a JSON `true` or `false`.
+    Bool,
+    /// Sequence of bytes, as a base64 encoded JSON string.
+    Bytes,
+    /// Instant in time, as an RFC 3339 JSON string in UTC such as `"2022-12-12T09:30:00Z"`.
+    Timestamp,
+    /// Calendar date without a time zone, as an ISO 8601 JSON string such as `"2022-12-12"`.
+    Date,
+    /// Length of time, as an ISO 8601 duration JSON string such as `"PT1H30M"`.
+    Duration,
+    /// Universally unique identifier, as a hyphenated lowercase JSON string such as
+    /// `"67e55044-10b1-426f-9247-bb680e5fe0c8"`.
+    Uuid,
+}
+
+impl From<crate::parser::types::Scalar> for Scalar {
+    fn from(scalar: crate::parser::types::Scalar) -> Self {
+        match scalar {
+            crate::parser::types::Scalar::String => Scalar::String,
+            crate::parser::types::Scalar::Int => Scalar::Int,
+            crate::parser::types::Scalar::UInt => Scalar::UInt,
+            crate::parser::types::Scalar::Long => Scalar::Long,
+            crate::parser::types::Scalar::ULong => Scalar::ULong,
+            crate::parser::types::Scalar::Float => Scalar::Float,
+            crate::parser::types::Scalar::Double => Scalar::Double,
+            crate::parser::types::Scalar::Decimal => Scalar::Decimal,
+            crate::parser::types::Scalar::Bool => Scalar::Bool,
+            crate::parser::types::Scalar::Bytes => Scalar::Bytes,
+            crate::parser::types::Scalar::Timestamp => Scalar::Timestamp,
+            crate::parser::types::Scalar::Date => Scalar::Date,
+            crate::parser::types::Scalar::Duration => Scalar::Duration,
+            crate::parser::types::Scalar::Uuid => Scalar::Uuid,
+        }
+    }
+}
+
+impl fmt::Display for Scalar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// User defined type from a parsed schema, referenced by name.
+#[derive(Clone, Copy)]
+enum UserType<'a, 'i> {
+    Custom(&'a crate::parser::types::CustomType<'i>),
+    Union(&'a crate::parser::types::Union<'i>),
+    Alias(&'a crate::parser::types::Alias<'i>),
+    Enum(&'a crate::parser::types::Enum<'i>),
+    Scalar(&'a crate::parser::types::CustomScalar<'i>),
+    /// Type parameter in scope within the fields of a generic custom type.
+    Param,
+}
+
+impl<'i> UserType<'_, 'i> {
+    fn package(&self) -> Option<crate::parser::Span<'i>> {
+        match self {
+            UserType::Custom(ty) => ty.package,
+            UserType::Union(ty) => ty.package,
+            UserType::Alias(ty) => ty.package,
+            UserType::Enum(ty) => ty.package,
+            UserType::Scalar(ty) => ty.package,
+            UserType::Param => None,
+        }
+    }
+
+    fn file(&self) -> Option<&'i path::Path> {
+        match self {
+            UserType::Custom(ty) => ty.file,
+            UserType::Union(ty) => ty.file,
+            UserType::Alias(ty) => ty.file,
+            UserType::Enum(ty) => ty.file,
+            UserType::Scalar(ty) => ty.file,
+            UserType::Param => None,
+        }
+    }
+}
+
+/// User defined types by qualified name, in scope of the declaration being validated.
+#[derive(Clone)]
+struct UserTypes<'t, 'a, 'i> {
+    declared: &'t HashMap<String, UserType<'a, 'i>>,
+    /// Package of the declaration, whose types unqualified names refer to first.
+    package: Option<String>,
+    /// Type parameters in scope within the fields of a generic custom type.
+    params: Vec<String>,
+}
+
+impl<'a, 'i> UserTypes<'_, 'a, 'i> {
+    /// Scope of a declaration in a package.
+    fn in_package(&self, package: Option<crate::parser::Span>) -> Self {
+        UserTypes {
+            package: package.map(|package| package.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// Scope of the fields of a declared type, where a generic type's parameters are in scope.
+    fn in_declaration(&self, ty: &UserType) -> Self {
+        UserTypes {
+            package: ty.package().map(|package| package.to_string()),
+            params: match ty {
+                UserType::Custom(ty) => ty.params.iter().map(|param| param.to_string()).collect(),
+                _ => vec![],
+            },
+            ..self.clone()
+        }
+    }
+
+    /// Type with a qualified name, as referenced by a [`TypeRef`].
+    fn get(&self, name: &str) -> Option<UserType<'a, 'i>> {
+        self.declared.get(name).copied()
+    }
+
+    /// Type a name in the schema refers to, along with its qualified name.
+    fn resolve(
+        &self,
+        name: &crate::parser::Span,
+    ) -> Result<Option<(String, UserType<'a, 'i>)>, Error> {
+        if self.params.iter().any(|param| param == name.fragment()) {
+            return Ok(Some((name.to_string(), UserType::Param)));
+        }
+
+        let names = self.declared.keys().map(String::as_str);
+        Ok(resolve_name(names, self.package.as_deref(), name)?
+            .map(|qualified| (qualified.to_string(), self.declared[qualified])))
+    }
+}
+
+/// Rejects recursive types which can never be constructed, such as a type with a required field of
+/// its own type, and aliases which require themselves.
+///
+/// Recursion through optional types, arrays, maps or alternative union variants is allowed.
+fn check_recursive_types<'a, 'i>(
+    declared_types: &[(crate::parser::Span, UserType<'a, 'i>)],
+    user_types: &UserTypes<'_, 'a, 'i>,
+) -> Result<(), Error> {
+    // Names which do not resolve are reported once types are resolved.
+    let required =
+        |scope: &UserTypes<'_, 'a, 'i>, ty: &crate::parser::types::Type| match required_type(ty)? {
+            crate::parser::types::ScalarOrUserType::UserDefined(name)
+            | crate::parser::types::ScalarOrUserType::Generic { ident: name, .. } => {
+                scope.resolve(name).ok().flatten()
+            }
+            crate::parser::types::ScalarOrUserType::Scalar(_) => None,
+        };
+
+    for (ident, ty) in declared_types {
+        if let UserType::Alias(alias) = ty {
+            let name = qualified_name(alias.package, ident.fragment());
+            let mut visited = HashSet::new();
+            let mut pending: Vec<_> = required(&user_types.in_declaration(ty), &alias.ty)
+                .into_iter()
+                .collect();
+            while let Some((required_name, required_ty)) = pending.pop() {
+                if required_name == name {
+                    return Err(in_declaring_file(
+                        alias.file,
+                        Error::CyclicAlias {
+                            name: ident.to_string(),
+                            position: (*ident).into(),
+                        },
+                    ));
+                }
+
+                if visited.insert(required_name) {
+                    if let UserType::Alias(alias) = required_ty {
+                        pending.extend(required(
+                            &user_types.in_declaration(&required_ty),
+                            &alias.ty,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    // A type is buildable once every type it requires is buildable,
+    // repeated until no more types can be built.
+    let mut buildable = HashSet::new();
+    loop {
+        let buildable_count = buildable.len();
+        for (ident, ty) in declared_types {
+            let scope = user_types.in_declaration(ty);
+            let is_required_buildable = |ty: &crate::parser::types::Type| match required_type(ty) {
+                Some(ty) => is_type_buildable(ty, &scope, &HashMap::new(), &buildable),
+                None => true,
+            };
+            let are_fields_buildable = |fields: &[crate::parser::event::Field]| {
+                fields.iter().all(|field| is_required_buildable(&field.ty))
+            };
+            let is_buildable = match ty {
+                UserType::Custom(ty) => are_fields_buildable(&ty.fields),
+                UserType::Union(ty) => ty
+                    .variants
+                    .iter()
+                    .any(|variant| are_fields_buildable(&variant.fields)),
+                UserType::Alias(ty) => is_required_buildable(&ty.ty),
+                UserType::Enum(_) | UserType::Scalar(_) | UserType::Param => true,
+            };
+            if is_buildable {
+                buildable.insert(qualified_name(ty.package(), ident.fragment()));
+            }
+        }
+
+        if buildable.len() == buildable_count {
+            break;
+        }
+    }
+
+    match declared_types
+        .iter()
+        .find(|(ident, ty)| !buildable.contains(&qualified_name(ty.package(), ident.fragment())))
+    {
+        Some((ident, ty)) => Err(in_declaring_file(
+            ty.file(),
+            Error::UnbuildableType {
+                name: ident.to_string(),
+                position: (*ident).into(),
+            },
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Whether a value of a type can be built from the types found to be buildable so far.
+///
+/// A generic type also needs a buildable argument for each type parameter it requires, given
+/// whether each of the parameters in scope is buildable.
+fn is_type_buildable(
+    ty: &crate::parser::types::ScalarOrUserType,
+    scope: &UserTypes<'_, '_, '_>,
+    params: &HashMap<String, bool>,
+    buildable: &HashSet<String>,
+) -> bool {
+    let (name, args) = match ty {
+        crate::parser::types::ScalarOrUserType::Scalar(_) => return true,
+        crate::parser::types::ScalarOrUserType::UserDefined(name) => (name, [].as_slice()),
+        crate::parser::types::ScalarOrUserType::Generic { ident, args } => (ident, args.as_slice()),
+    };
+
+    match scope.resolve(name).ok().flatten() {
+        None => true,
+        Some((name, UserType::Param)) => params.get(&name).copied().unwrap_or(true),
+        Some((name, user_type @ UserType::Custom(ty))) if !args.is_empty() => {
+            let generic_params = ty
+                .params
+                .iter()
+                .map(|param| param.to_string())
+                .zip(args.iter().map(|arg| match required_type(arg) {
+                    Some(arg) => is_type_buildable(arg, scope, params, buildable),
+                    None => true,
+                }))
+                .collect();
+            let generic_scope = scope.in_declaration(&user_type);
+
+            buildable.contains(&name)
+                && ty
+                    .fields
+                    .iter()
+                    .all(|field| match required_type(&field.ty) {
+                        Some(ty) => {
+                            is_type_buildable(ty, &generic_scope, &generic_params, buildable)
+                        }
+                        None => true,
+                    })
+        }
+        Some((name, _)) => buildable.contains(&name),
+    }
+}
+
+/// User defined type which must be present for a value of this type to exist.
+fn required_type<'t, 'i>(
+    ty: &'t crate::parser::types::Type<'i>,
+) -> Option<&'t crate::parser::types::ScalarOrUserType<'i>> {
+    match ty {
+        crate::parser::types::Type::Single(
+            crate::parser::types::OptionalOrRequiredType::Required(
+                ty @ (crate::parser::types::ScalarOrUserType::UserDefined(_)
+                | crate::parser::types::ScalarOrUserType::Generic { .. }),
+            ),
+        ) => Some(ty),
+        _ => None,
+    }
+}
+
+/// Names of all user defined types referenced by a type.
+fn referenced_types<'i>(ty: &crate::parser::types::Type<'i>) -> Vec<crate::parser::Span<'i>> {
+    fn user_defined<'i>(
+        ty: &crate::parser::types::ScalarOrUserType<'i>,
+    ) -> Vec<crate::parser::Span<'i>> {
+        match ty {
+            crate::parser::types::ScalarOrUserType::UserDefined(name) => vec![*name],
+            crate::parser::types::ScalarOrUserType::Generic { ident, args } => {
+                std::iter::once(*ident)
+                    .chain(args.iter().flat_map(referenced_types))
+                    .collect()
+            }
+            crate::parser::types::ScalarOrUserType::Scalar(_) => vec![],
+        }
+    }
+    let optional_or_required = |ty: &crate::parser::types::OptionalOrRequiredType<'i>| match ty {
+        crate::parser::types::OptionalOrRequiredType::Optional(ty)
+        | crate::parser::types::OptionalOrRequiredType::Required(ty) => user_defined(ty),
+    };
+
+    match ty {
+        crate::parser::types::Type::Single(ty) => optional_or_required(ty),
+        crate::parser::types::Type::Array { inner, .. } => referenced_types(inner),
+        crate::parser::types::Type::Map { key, value, .. } => user_defined(key)
+            .into_iter()
+            .chain(referenced_types(value))
+            .collect(),
+    }
+}
+
+/// Exact ordering of two numbers in plain decimal notation, eg. `-12.50`.
+fn compare_decimals(a: &str, b: &str) -> Option<Ordering> {
+    // Sign, integer digits without leading zeros and fraction digits without trailing zeros.
+    fn parts(value: &str) -> Option<(bool, &str, &str)> {
+        let (negative, digits) = match value.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, value),
+        };
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        let is_digits = |digits: &str| digits.bytes().all(|b| b.is_ascii_digit());
+        if int.is_empty() || !is_digits(int) || !is_digits(frac) {
+            return None;
+        }
+
+        let (int, frac) = (int.trim_start_matches('0'), frac.trim_end_matches('0'));
+        Some((negative && !(int.is_empty() && frac.is_empty()), int, frac))
+    }
+
+    let (a_negative, a_int, a_frac) = parts(a)?;
+    let (b_negative, b_int, b_frac) = parts(b)?;
+    let magnitude = a_int
+        .len()
+        .cmp(&b_int.len())
+        .then_with(|| a_int.cmp(b_int))
+        .then_with(|| a_frac.cmp(b_frac));
+
+    Some(match (a_negative, b_negative) {
+        (false, false) => magnitude,
+        (true, true) => magnitude.reverse(),
+        (false, true) => Ordering::Greater,
+        (true, false) => Ordering::Less,
+    })
+}
+
+/// Declaration a name refers to from within a package, see [`resolve_name`].
+fn lookup<'m, T>(
+    declarations: &'m HashMap<String, T>,
+    package: Option<&str>,
+    name: &crate::parser::Span,
+) -> Result<Option<&'m T>, Error> {
+    let names = declarations.keys().map(String::as_str);
+    Ok(resolve_name(names, package, name)?.map(|name| &declarations[name]))
+}
+
+/// Qualified name a name refers to from within a package, among the qualified names declared.
+///
+/// A qualified name must match exactly. An unqualified name refers to the declaration in the same
+/// package, or otherwise to the only package declaring it.
+fn resolve_name<'n>(
+    names: impl IntoIterator<Item = &'n str>,
+    package: Option<&str>,
+    name: &crate::parser::Span,
+) -> Result<Option<&'n str>, Error> {
+    let reference = *name.fragment();
+    let is_qualified = reference.contains('.');
+    let own = match package {
+        Some(package) if !is_qualified => format!("{package}.{reference}"),
+        _ => reference.to_string(),
+    };
+
+    let mut candidates: Vec<_> = names
+        .into_iter()
+        .filter(|declared| {
+            *declared == own || (!is_qualified && unqualified_name(declared) == reference)
+        })
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    if let Some(declared) = candidates.iter().find(|declared| **declared == own) {
+        return Ok(Some(declared));
+    }
+
+    match candidates.as_slice() {
+        [] => Ok(None),
+        [declared] => Ok(Some(declared)),
+        _ => Err(Error::AmbiguousName {
+            name: reference.to_string(),
+            position: (*name).into(),
+        }),
+    }
+}
+
+/// Name of a type without its package, eg. `Money` for `common.Money`.
+fn unqualified_name(name: &str) -> &str {
+    name.rsplit_once('.').map_or(name, |(_, name)| name)
+}
+
+/// Name prefixed by the package it is declared in, if any.
+fn qualified_name(package: Option<crate::parser::Span>, name: &str) -> String {
+    match package {
+        Some(package) => format!("{package}.{name}"),
+        None => name.to_string(),
+    }
+}
+
+/// Attributes an error in an imported declaration to the file declaring it.
+fn in_declaring_file(file: Option<&path::Path>, err: Error) -> Error {
+    match file {
+        Some(path) => import::in_file(path, err),
+        None => err,
+    }
+}
+
+/// Strips the quotes from a string literal and resolves its escapes.
+fn parse_string_literal(literal: &str) -> String {
+    let mut value = String::with_capacity(literal.len());
+    let mut chars = literal[1..literal.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        value.push(match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some(c) => c,
+                None => break,
+            },
+            c => c,
+        });
+    }
+
+    value
+}
+
+/// Validates constraints against the type of a field or param, and that its default satisfies them.
+fn parse_constraints(
+    user_types: &UserTypes<'_, '_, '_>,
+    name: &str,
+    ty: &RepeatableType,
+    constraints: &[crate::parser::annotation::Annotation],
+    default: Option<(&Value, &crate::parser::literal::Literal)>,
+) -> Result<Vec<Constraint>, Error> {
+    constraints
+        .iter()
+        .map(|annotation| {
+            let constraint = Constraint::from_annotation(user_types, ty, annotation)?;
+            match default {
+                Some((value, literal)) if !constraint.check(value) => {
+                    Err(Error::DefaultViolatesConstraint {
+                        name: name.to_string(),
+                        constraint: annotation.ident.to_string(),
+                        position: literal.span().into(),
+                    })
+                }
+                _ => Ok(constraint),
+            }
+        })
+        .collect()
+}
+
+/// Validates the arguments of each annotation.
+fn parse_annotations(
+    annotations: &[crate::parser::annotation::Annotation],
+) -> Result<Vec<Annotation>, Error> {
+    annotations
+        .iter()
+        .map(Annotation::from_annotation)
+        .collect()
+}
+
+/// Joins doc comment lines, stripping the leading space of each line.
+fn parse_docs(docs: &[crate::parser::Span]) -> Option<String> {
+    if docs.is_empty() {
+        return None;
+    }
+
+    let lines: Vec<_> = docs
+        .iter()
+        .map(|line| {
+            let line = line.fragment().trim_end();
+            line.strip_prefix(' ').unwrap_or(line)
+        })
+        .collect();
+
+    Some(lines.join("\n"))
+}
+
+/// Location of an item in the schema source.
+///
+/// Lines and columns start at 1, the offset is in bytes from the start of the input. Positions of
+/// imported declarations are within the file recorded in their `file`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Position {
+    pub offset: usize,
+    pub line: u32,
+    pub column: usize,
+}
+
+impl From<crate::parser::Span<'_>> for Position {
+    fn from(span: crate::parser::Span<'_>) -> Self {
+        Position {
+            offset: span.location_offset(),
+            line: span.location_line(),
+            column: span.get_utf8_column(),
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        schema::{
+            CommandEvents, Constraint, Pattern, Position, RepeatableType, SagaTarget, Scalar,
+            TypeOpt, TypeRef, Value,
+        },
+        Error,
+    };
+
+    #[test]
+    fn it_reports_positions_of_undefined_types() {
+        let schema_str = r#"version = "0.1.0"
+
+aggregate BankAccount {
+  open_account(user: User) -> OpenedAccount
+}
+
+event OpenedAccount {
+  user: Usr
+}
+
+type User {
+  name: String
+}
+"#;
+
+        let err = crate::parse(schema_str).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TypeNotDefined {
+                ref name,
+                position: Position {
+                    offset: 120,
+                    line: 8,
+                    column: 9,
+                },
+            } if name == "Usr"
+        ));
+        assert_eq!(err.to_string(), "type not defined Usr at 8:9");
+    }
+
+    #[test]
+    fn it_joins_doc_comments() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            /// Bank account.
+            ///
+            /// Holds funds.
+            aggregate BankAccount {
+                /// Opens an account.
+                open_account(initial_balance: Float) -> OpenedAccount
+            }
+
+            event OpenedAccount {
+                ///Starting balance.
+                initial_balance: Float
+            }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        assert_eq!(
+            schema.aggregates["BankAccount"].docs.as_deref(),
+            Some("Bank account.\n\nHolds funds.")
+        );
+        assert_eq!(
+            schema.aggregates["BankAccount"].commands["open_account"]
+                .docs
+                .as_deref(),
+            Some("Opens an account.")
+        );
+        assert_eq!(
+            schema.events["OpenedAccount"].fields["initial_balance"]
+                .docs
+                .as_deref(),
+            Some("Starting balance.")
+        );
+        assert_eq!(schema.events["OpenedAccount"].docs, None);
+    }
+
+    #[test]
+    fn it_resolves_enums() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                open_account(currency: Currency) -> OpenedAccount
+            }
+
+            event OpenedAccount {
+                currency: Currency?
+            }
+
+            enum Currency { Aud, Usd }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let currency = &schema.enums["Currency"];
+        assert_eq!(
+            currency
+                .variants
+                .iter()
+                .map(|variant| variant.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Aud", "Usd"]
+        );
+        assert_eq!(
+            schema.aggregates["BankAccount"].commands["open_account"].params[0].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Enum("Currency".to_string())))
+        );
+        assert_eq!(
+            schema.events["OpenedAccount"].fields["currency"].ty,
+            RepeatableType::Single(TypeOpt::Optional(TypeRef::Enum("Currency".to_string())))
+        );
+    }
+
+    #[test]
+    fn it_rejects_duplicate_enum_variants() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {}
+
+            enum Currency { Aud, Usd, Aud }
+        "#;
+
+        let err = crate::parse(schema_str).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DuplicateEnumVariant { ref ty, ref variant, .. }
+                if ty == "Currency" && variant == "Aud"
+        ));
+    }
+
+    #[test]
+    fn it_resolves_unions() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                pay(method: PaymentMethod) -> Paid
+            }
+
+            event Paid {
+                method: PaymentMethod
+            }
+
+            type PaymentMethod = Card { last4: String } | BankTransfer { bsb: String, account: String }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let payment_method = &schema.unions["PaymentMethod"];
+        assert_eq!(payment_method.variants.len(), 2);
+        assert_eq!(payment_method.variants[0].name, "Card");
+        assert!(payment_method.variants[0].fields.contains_key("last4"));
+        assert_eq!(payment_method.variants[1].name, "BankTransfer");
+        assert_eq!(payment_method.variants[1].fields.len(), 2);
+        assert_eq!(
+            schema.events["Paid"].fields["method"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Union(
+                "PaymentMethod".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn it_rejects_duplicate_union_variant_fields() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {}
+
+            type PaymentMethod = Card { last4: String, last4: String } | Cash {}
+        "#;
+
+        let err = crate::parse(schema_str).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DuplicateUnionVariantField { ref ty, ref variant, ref field, .. }
+                if ty == "PaymentMethod" && variant == "Card" && field == "last4"
+        ));
+    }
+
+    #[test]
+    fn it_resolves_maps() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {}
+
+            event LimitsChanged {
+                limits: {Currency: Float?}?
+                flags: {String: Bool}
+                history: {String: [Float]?}
+                grid: [{Currency: Int}]
+                balances: {Account: Float}
+            }
+
+            enum Currency { Aud, Usd }
+            type Account = String;
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let event = &schema.events["LimitsChanged"];
+        assert_eq!(
+            event.fields["limits"].ty,
+            RepeatableType::OptionalMap {
+                key: TypeRef::Enum("Currency".to_string()),
+                value: Box::new(RepeatableType::Single(TypeOpt::Optional(TypeRef::Scalar(
+                    Scalar::Float
+                )))),
+            }
+        );
+        assert_eq!(
+            event.fields["flags"].ty,
+            RepeatableType::RequiredMap {
+                key: TypeRef::Scalar(Scalar::String),
+                value: Box::new(RepeatableType::Single(TypeOpt::Required(TypeRef::Scalar(
+                    Scalar::Bool
+                )))),
+            }
+        );
+        assert_eq!(event.fields["history"].ty.to_string(), "{String: [Float]?}");
+        assert_eq!(
+            event.fields["grid"].ty,
+            RepeatableType::RequiredArray(Box::new(RepeatableType::RequiredMap {
+                key: TypeRef::Enum("Currency".to_string()),
+                value: Box::new(RepeatableType::Single(TypeOpt::Required(TypeRef::Scalar(
+                    Scalar::Int
+                )))),
+            }))
+        );
+        assert_eq!(
+            event.fields["balances"].ty,
+            RepeatableType::RequiredMap {
+                key: TypeRef::Alias("Account".to_string()),
+                value: Box::new(RepeatableType::Single(TypeOpt::Required(TypeRef::Scalar(
+                    Scalar::Float
+                )))),
+            }
+        );
+    }
+
+    #[test]
+    fn it_resolves_extended_scalars() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate Invoice {}
+
+            event InvoiceIssued {
+                id: Uuid
+                amount: Decimal
+                issued_at: Timestamp
+                due: Date?
+                terms: Duration
+                lines: UInt
+                sequence: ULong
+            }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let event = &schema.events["InvoiceIssued"];
+        let scalar = |field: &str| match &event.fields[field].ty {
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Scalar(scalar)))
+            | RepeatableType::Single(TypeOpt::Optional(TypeRef::Scalar(scalar))) => *scalar,
+            ty => panic!("expected scalar, found {ty:?}"),
+        };
+        assert_eq!(scalar("id"), Scalar::Uuid);
+        assert_eq!(scalar("amount"), Scalar::Decimal);
+        assert_eq!(scalar("issued_at"), Scalar::Timestamp);
+        assert_eq!(scalar("due"), Scalar::Date);
+        assert_eq!(scalar("terms"), Scalar::Duration);
+        assert_eq!(scalar("lines"), Scalar::UInt);
+        assert_eq!(scalar("sequence"), Scalar::ULong);
+    }
+
+    #[test]
+    fn it_resolves_nested_arrays() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            event PricesUpdated {
+                grid: [[Decimal]] = [[1, 2.50], []]
+                labels: [[String?]]?
+                tiers: [[[Int]?]]
+                rows: [Row]
+            }
+
+            type Row = [Int]
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let event = &schema.events["PricesUpdated"];
+        assert_eq!(
+            event.fields["grid"].ty,
+            RepeatableType::RequiredArray(Box::new(RepeatableType::RequiredArray(Box::new(
+                RepeatableType::Single(TypeOpt::Required(TypeRef::Scalar(Scalar::Decimal)))
+            ))))
+        );
+        assert_eq!(
+            event.fields["grid"].default,
+            Some(Value::Array(vec![
+                Value::Array(vec![Value::Int(1), Value::Float("2.50".to_string())]),
+                Value::Array(vec![]),
+            ]))
+        );
+        assert_eq!(
+            event.fields["labels"].ty,
+            RepeatableType::OptionalArray(Box::new(RepeatableType::RequiredArray(Box::new(
+                RepeatableType::Single(TypeOpt::Optional(TypeRef::Scalar(Scalar::String)))
+            ))))
+        );
+        assert_eq!(event.fields["labels"].ty.to_string(), "[[String?]]?");
+        assert_eq!(event.fields["tiers"].ty.to_string(), "[[[Int]?]]");
+
+        let err = crate::parse(
+            r#"
+            version = "0.1.0"
+
+            event PricesUpdated {
+                grid: [[Int]] = [1, 2]
+            }
+            "#,
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::InvalidDefault { ty, .. } if ty == "[Int]"));
+    }
+
+    #[test]
+    fn it_rejects_custom_type_map_keys() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {}
+
+            event LimitsChanged {
+                limits: {User: Float}
+            }
+
+            type User {
+                name: String
+            }
+        "#;
+
+        let err = crate::parse(schema_str).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::InvalidMapKey { ref name, .. } if name == "User"
+        ));
+
+        let schema_str = r#"
+            version = "0.1.0"
+
+            event LimitsChanged {
+                limits: {Owner: Float}
+            }
+
+            type Owner = User;
+
+            type User {
+                name: String
+            }
+        "#;
+        assert!(matches!(
+            crate::parse(schema_str).unwrap_err(),
+            Error::InvalidMapKey { name, .. } if name == "Owner"
+        ));
+
+        let schema_str = r#"
+            version = "0.1.0"
+
+            event LimitsChanged {
+                limits: {Code: Float}
+            }
+
+            type Code = String?;
+        "#;
+        assert!(matches!(
+            crate::parse(schema_str).unwrap_err(),
+            Error::InvalidMapKey { name, .. } if name == "Code"
+        ));
+    }
+
+    #[test]
+    fn it_resolves_multiple_aggregates() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                open(name: String) -> Opened
+            }
+
+            aggregate CreditCard {
+                open(name: String) -> Opened
+            }
+
+            event Opened {
+                name: String
+            }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        assert_eq!(schema.aggregates.len(), 2);
+        assert!(schema.aggregates["BankAccount"]
+            .commands
+            .contains_key("open"));
+        assert!(schema.aggregates["CreditCard"]
+            .commands
+            .contains_key("open"));
+    }
+
+    #[test]
+    fn it_resolves_schemas_without_aggregates() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            event Opened {
+                name: String
+            }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        assert!(schema.aggregates.is_empty());
+        assert!(schema.events.contains_key("Opened"));
+    }
+
+    #[test]
+    fn it_rejects_duplicate_aggregates() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {}
+
+            aggregate BankAccount {}
+        "#;
+
+        let err = crate::parse(schema_str).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DuplicateAggregate {
+                ref name,
+                position: Position { line: 6, .. },
+            } if name == "BankAccount"
+        ));
+    }
+
+    #[test]
+    fn it_resolves_aliases() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                deposit(amount: Money) -> Deposited
+            }
+
+            event Deposited {
+                amount: Money
+                tags: Tags?
+            }
+
+            type Money = Float;
+            type Tags = [String];
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let money = &schema.aliases["Money"];
+        assert_eq!(
+            money.ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Scalar(Scalar::Float)))
+        );
+        assert_eq!(
+            schema.aggregates["BankAccount"].commands["deposit"].params[0].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Alias("Money".to_string())))
+        );
+        assert_eq!(
+            schema.events["Deposited"].fields["tags"].ty,
+            RepeatableType::Single(TypeOpt::Optional(TypeRef::Alias("Tags".to_string())))
+        );
+    }
+
+    #[test]
+    fn it_rejects_duplicate_type_names() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            struct Money {
+                amount: Float
+            }
+
+            type Money = Float;
+        "#;
+
+        let err = crate::parse(schema_str).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DuplicateCustomType { ref name, .. } if name == "Money"
+        ));
+    }
+
+    #[test]
+    fn it_resolves_custom_scalars() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            /// Email address.
+            scalar Email
+            scalar Iban : String
+
+            event AccountOpened {
+                email: Email
+                ibans: {Iban: Email?}
+            }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        assert_eq!(
+            schema.scalars["Email"].docs.as_deref(),
+            Some("Email address.")
+        );
+        assert_eq!(schema.scalars["Email"].base, None);
+        assert_eq!(schema.scalars["Iban"].base, Some(Scalar::String));
+
+        let event = &schema.events["AccountOpened"];
+        assert_eq!(
+            event.fields["email"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::CustomScalar(
+                "Email".to_string()
+            )))
+        );
+        assert_eq!(
+            event.fields["ibans"].ty,
+            RepeatableType::RequiredMap {
+                key: TypeRef::CustomScalar("Iban".to_string()),
+                value: Box::new(RepeatableType::Single(TypeOpt::Optional(
+                    TypeRef::CustomScalar("Email".to_string())
+                ))),
+            }
+        );
+    }
+
+    #[test]
+    fn it_rejects_duplicate_scalars() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            struct Email {
+                address: String
+            }
+
+            scalar Email
+        "#;
+
+        let err = crate::parse(schema_str).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DuplicateScalar { ref name, .. } if name == "Email"
+        ));
+    }
+
+    #[test]
+    fn it_resolves_defaults() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                open_account(retries: Int = 3, currency: Currency = Aud) -> AccountOpened
+            }
+
+            event AccountOpened {
+                currency: String = "AUD"
+                tags: [String] = []
+                limits: Limits = [1, 2.50]
+                note: Note? = "say \"hi\""
+                active: Bool = false
+            }
+
+            enum Currency { Aud, Usd }
+            type Limits = [Decimal]
+            scalar Note
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let command = &schema.aggregates["BankAccount"].commands["open_account"];
+        assert_eq!(command.params[0].default, Some(Value::Int(3)));
+        assert_eq!(
+            command.params[1].default,
+            Some(Value::EnumVariant("Aud".to_string()))
+        );
+
+        let event = &schema.events["AccountOpened"];
+        assert_eq!(
+            event.fields["currency"].default,
+            Some(Value::String("AUD".to_string()))
+        );
+        assert_eq!(event.fields["tags"].default, Some(Value::Array(vec![])));
+        assert_eq!(
+            event.fields["limits"].default,
+            Some(Value::Array(vec![
+                Value::Int(1),
+                Value::Float("2.50".to_string())
+            ]))
+        );
+        assert_eq!(
+            event.fields["note"].default,
+            Some(Value::String("say \"hi\"".to_string()))
+        );
+        assert_eq!(event.fields["active"].default, Some(Value::Bool(false)));
+    }
+
+    #[test]
+    fn it_rejects_invalid_defaults() {
+        let invalid_default = |field: &str| {
+            let schema_str = format!(
+                r#"
+                version = "0.1.0"
+
+                event AccountOpened {{
+                    {field}
+                }}
+
+                enum Currency {{ Aud, Usd }}
+                "#
+            );
+            match crate::parse(&schema_str).unwrap_err() {
+                Error::InvalidDefault { ty, position, .. } => (
+                    ty,
+                    schema_str[position.offset..]
+                        .split_whitespace()
+                        .next()
+                        .unwrap()
+                        .to_string(),
+                ),
+                err => panic!("expected invalid default, found {err:?}"),
+            }
+        };
+
+        assert_eq!(
+            invalid_default("retries: Int = \"3\""),
+            ("Int".to_string(), "\"3\"".to_string())
+        );
+        assert_eq!(
+            invalid_default("retries: Int = 2147483648"),
+            ("Int".to_string(), "2147483648".to_string())
+        );
+        assert_eq!(
+            invalid_default("count: UInt = -1"),
+            ("UInt".to_string(), "-1".to_string())
+        );
+        assert_eq!(
+            invalid_default("tags: [Int] = [1, 2.5]"),
+            ("Int".to_string(), "2.5]".to_string())
+        );
+        assert_eq!(
+            invalid_default("tags: [Int]? = 1"),
+            ("[Int]?".to_string(), "1".to_string())
+        );
+        assert_eq!(
+            invalid_default("currency: Currency = Eur"),
+            ("Currency".to_string(), "Eur".to_string())
+        );
+    }
+
+    #[test]
+    fn it_resolves_annotations() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            @since("0.2.0")
+            aggregate BankAccount {
+                @deprecated("use open_savings_account")
+                open_account(@rename("initialBalance") initial_balance: Float) -> AccountOpened
+            }
+
+            /// Opened a new account.
+            @since("0.2.0", 2)
+            event AccountOpened {
+                @tags(["audit", "pii"], Apac, true)
+                @internal
+                initial_balance: Float
+            }
+
+            enum Region {
+                @default
+                Apac
+            }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let aggregate = &schema.aggregates["BankAccount"];
+        assert_eq!(aggregate.annotations[0].name, "since");
+        assert_eq!(
+            aggregate.annotations[0].args,
+            vec![Value::String("0.2.0".to_string())]
+        );
+
+        let command = &aggregate.commands["open_account"];
+        assert_eq!(command.annotations[0].name, "deprecated");
+        assert_eq!(command.params[0].annotations[0].name, "rename");
+
+        let event = &schema.events["AccountOpened"];
+        assert_eq!(event.docs.as_deref(), Some("Opened a new account."));
+        assert_eq!(
+            event.annotations[0].args,
+            vec![Value::String("0.2.0".to_string()), Value::Int(2)]
+        );
+
+        let field = &event.fields["initial_balance"];
+        assert_eq!(
+            field
+                .annotations
+                .iter()
+                .map(|annotation| annotation.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["tags", "internal"]
+        );
+        assert_eq!(
+            field.annotations[0].args,
+            vec![
+                Value::Array(vec![
+                    Value::String("audit".to_string()),
+                    Value::String("pii".to_string())
+                ]),
+                Value::EnumVariant("Apac".to_string()),
+                Value::Bool(true),
+            ]
+        );
+        assert!(field.annotations[1].args.is_empty());
+
+        assert_eq!(
+            schema.enums["Region"].variants[0].annotations[0].name,
+            "default"
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_annotations() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            @since(0.3.0)
+            event AccountOpened {}
+        "#;
+        assert!(matches!(
+            crate::parse(schema_str).unwrap_err(),
+            Error::Parse(_)
+        ));
+
+        let schema_str = r#"
+            version = "0.1.0"
+
+            @max(340282366920938463463374607431768211456)
+            event AccountOpened {}
+        "#;
+        match crate::parse(schema_str).unwrap_err() {
+            Error::InvalidAnnotationArgument { name, position } => {
+                assert_eq!(name, "max");
+                assert!(schema_str[position.offset..]
+                    .starts_with("340282366920938463463374607431768211456"));
+            }
+            err => panic!("expected invalid annotation argument, found {err:?}"),
+        }
+    }
+
+    #[test]
+    fn it_resolves_constraints() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                deposit(amount: Amount @min(0.01), currency: String @pattern("^[A-Z]{3}$") = "AUD") -> Deposited
+            }
+
+            event Deposited {
+                name: String? @length(1, 100)
+                items: [Item] @min_items(1) @max_items(50)
+                retries: UInt @max(10) = 3
+            }
+
+            type Amount = Money
+            scalar Money: Decimal
+            type Item { sku: String }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let command = &schema.aggregates["BankAccount"].commands["deposit"];
+        assert_eq!(
+            command.params[0].constraints,
+            vec![Constraint::Min(Value::Float("0.01".to_string()))]
+        );
+        assert_eq!(
+            command.params[1].constraints,
+            vec![Constraint::Pattern(Pattern::new("^[A-Z]{3}$").unwrap())]
+        );
+
+        let event = &schema.events["Deposited"];
+        assert_eq!(
+            event.fields["name"].constraints,
+            vec![Constraint::Length { min: 1, max: 100 }]
+        );
+        assert_eq!(
+            event.fields["items"].constraints,
+            vec![Constraint::MinItems(1), Constraint::MaxItems(50)]
+        );
+        assert_eq!(
+            event.fields["retries"].constraints,
+            vec![Constraint::Max(Value::Int(10))]
+        );
+
+        let min = &command.params[0].constraints[0];
+        assert!(min.check(&Value::Int(5)));
+        assert!(min.check(&Value::Float("0.01".to_string())));
+        assert!(!min.check(&Value::Float("0.001".to_string())));
+        assert!(!min.check(&Value::Float("0.00999999999999999999".to_string())));
+        assert!(min.check(&Value::Float("0.0100".to_string())));
+        assert!(!min.check(&Value::Int(-1)));
+        assert!(!min.check(&Value::String("5".to_string())));
+
+        let pattern = &command.params[1].constraints[0];
+        assert!(pattern.check(&Value::String("USD".to_string())));
+        assert!(!pattern.check(&Value::String("usd".to_string())));
+    }
+
+    #[test]
+    fn it_rejects_invalid_constraints() {
+        let invalid_constraint = |field: &str| {
+            let schema_str = format!(
+                r#"
+                version = "0.1.0"
+
+                event Deposited {{
+                    {field}
+                }}
+
+                enum Currency {{ Aud, Usd }}
+                "#
+            );
+            let err = crate::parse(&schema_str).unwrap_err();
+            let position = err.position().unwrap();
+            (
+                err,
+                schema_str[position.offset..]
+                    .split_whitespace()
+                    .next()
+                    .unwrap()
+                    .to_string(),
+            )
+        };
+
+        let (err, at) = invalid_constraint(r#"amount: Int @pattern("^[0-9]+$")"#);
+        assert!(matches!(err, Error::InvalidConstraint { ty, .. } if ty == "Int"));
+        assert_eq!(at, r#"pattern("^[0-9]+$")"#);
+
+        let (err, _) = invalid_constraint("currency: Currency @min(0)");
+        assert!(matches!(err, Error::InvalidConstraint { ty, .. } if ty == "Currency"));
+
+        let (err, _) = invalid_constraint("tags: [String] @length(1, 10)");
+        assert!(matches!(err, Error::InvalidConstraint { ty, .. } if ty == "[String]"));
+
+        let (err, _) = invalid_constraint("amount: Float @positive");
+        assert!(matches!(err, Error::UnknownConstraint { name, .. } if name == "positive"));
+
+        let (err, at) = invalid_constraint("amount: UInt @min(-1)");
+        assert!(matches!(err, Error::InvalidConstraintArgument { .. }));
+        assert_eq!(at, "-1)");
+
+        let (err, at) = invalid_constraint("name: String @length(10, 1)");
+        assert!(matches!(err, Error::InvalidConstraintArgument { .. }));
+        assert_eq!(at, "1)");
+
+        let (err, _) = invalid_constraint(r#"code: String @pattern("[A-Z")"#);
+        assert!(matches!(err, Error::InvalidConstraintArgument { .. }));
+
+        let (err, _) = invalid_constraint("tags: [String] @max_items");
+        assert!(matches!(err, Error::InvalidConstraintArgument { .. }));
+
+        let (err, at) = invalid_constraint("amount: Float @min(0) = -1.5");
+        assert!(
+            matches!(err, Error::DefaultViolatesConstraint { constraint, .. } if constraint == "min")
+        );
+        assert_eq!(at, "-1.5");
+    }
+
+    #[test]
+    fn it_resolves_packages() {
+        let schema_str = r#"
+            package banking.accounts
+
+            version = "0.1.0"
+
+            event Opened {
+                owner: banking.accounts.Owner
+                currency: Currency
+            }
+
+            type Owner { name: String }
+            enum Currency { Aud, Usd }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        assert_eq!(schema.package.as_deref(), Some("banking.accounts"));
+        let opened = &schema.events["banking.accounts.Opened"];
+        assert_eq!(opened.qualified_name, "banking.accounts.Opened");
+        assert_eq!(
+            opened.fields["owner"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Custom(
+                "banking.accounts.Owner".to_string()
+            )))
+        );
+        assert_eq!(
+            opened.fields["currency"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Enum(
+                "banking.accounts.Currency".to_string()
+            )))
+        );
+        assert_eq!(
+            schema.types["banking.accounts.Owner"].qualified_name,
+            "banking.accounts.Owner"
+        );
+        assert_eq!(
+            schema.enums["banking.accounts.Currency"].qualified_name,
+            "banking.accounts.Currency"
+        );
+
+        let schema = crate::parse("version = \"0.1.0\"\nevent Opened {}").unwrap();
+        assert_eq!(schema.package, None);
+        assert_eq!(schema.events["Opened"].qualified_name, "Opened");
+    }
+
+    #[test]
+    fn it_rejects_invalid_packages() {
+        let schema_str = r#"
+            package banking.accounts
+
+            version = "0.1.0"
+
+            event Opened {
+                owner: common.Owner
+            }
+
+            type Owner { name: String }
+        "#;
+        assert!(matches!(
+            crate::parse(schema_str).unwrap_err(),
+            Error::TypeNotDefined { name, .. } if name == "common.Owner"
+        ));
+
+        let schema_str = r#"
+            package banking.accounts
+            package banking.loans
+
+            version = "0.1.0"
+        "#;
+        assert!(matches!(
+            crate::parse(schema_str).unwrap_err(),
+            Error::MultiplePackages
+        ));
+    }
+
+    #[test]
+    fn it_resolves_generic_types() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate Account {
+                list_owners(page: Page<Owner>?) -> OwnersListed
+            }
+
+            event OwnersListed {
+                owners: Page<Change<Owner>>?
+                balance: Change<Money>
+            }
+
+            type Page<T> {
+                items: [T]
+                next: String?
+            }
+
+            type Change<T> {
+                old: T?
+                new: T
+            }
+
+            type Owner { name: String }
+
+            type Tree { children: Page<Tree>, parent: Change<Tree>? }
+
+            scalar Money: Decimal
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let change = &schema.types["Change"];
+        assert_eq!(change.params, vec!["T".to_string()]);
+        assert_eq!(
+            change.fields["new"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Param("T".to_string())))
+        );
+
+        let event = &schema.events["OwnersListed"];
+        let money = TypeRef::CustomScalar("Money".to_string());
+        assert_eq!(
+            event.fields["balance"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Generic {
+                name: "Change".to_string(),
+                args: vec![money.clone()],
+            }))
+        );
+        assert_eq!(
+            event.fields["owners"].ty.to_string(),
+            "Page<Change<Owner>>?"
+        );
+        assert_eq!(
+            schema.aggregates["Account"].commands["list_owners"].params[0]
+                .ty
+                .to_string(),
+            "Page<Owner>?"
+        );
+
+        let instance = change.instantiate(std::slice::from_ref(&money)).unwrap();
+        assert!(instance.params.is_empty());
+        assert_eq!(
+            instance.fields["old"].ty,
+            RepeatableType::Single(TypeOpt::Optional(money.clone()))
+        );
+        assert_eq!(
+            schema.types["Page"].instantiate(&[money]).unwrap().fields["items"].ty,
+            RepeatableType::RequiredArray(Box::new(RepeatableType::Single(TypeOpt::Required(
+                TypeRef::CustomScalar("Money".to_string())
+            ))))
+        );
+        assert!(change.instantiate(&[]).is_none());
+    }
+
+    #[test]
+    fn it_rejects_invalid_generic_types() {
+        let parse = |items: &str| {
+            crate::parse(&format!(
+                "version = \"0.1.0\"\n\ntype Change<T> {{ old: T?, new: T }}\n\n{items}"
+            ))
+            .unwrap_err()
+        };
+
+        assert!(matches!(
+            parse("event Opened { balance: Change }"),
+            Error::InvalidTypeArguments { name, expected: 1, found: 0, .. } if name == "Change"
+        ));
+        assert!(matches!(
+            parse("event Opened { balance: Change<Int, Int> }"),
+            Error::InvalidTypeArguments {
+                expected: 1,
+                found: 2,
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse("event Opened { owner: Owner<Int> }\ntype Owner { name: String }"),
+            Error::InvalidTypeArguments { name, expected: 0, found: 1, .. } if name == "Owner"
+        ));
+        assert!(matches!(
+            parse("event Opened { balance: T }"),
+            Error::TypeNotDefined { name, .. } if name == "T"
+        ));
+        for (ty, argument) in [
+            ("Change<[Int]>", "[Int]"),
+            ("Change<Int?>", "Int?"),
+            ("Change<{String: Int}>", "{String: Int}"),
+        ] {
+            assert!(matches!(
+                parse(&format!("event Opened {{ balance: {ty} }}")),
+                Error::UnsupportedTypeArgument { name, argument: found, position }
+                    if name == "Change" && found == argument && position.line == 5
+            ));
+        }
+        assert!(matches!(
+            parse("type Loop { change: Change<Loop> }"),
+            Error::UnbuildableType { name, .. } if name == "Loop"
+        ));
+        assert!(matches!(
+            parse("type Wrapper<T> { change: Change<T> }\ntype Loop { wrapper: Wrapper<Loop> }"),
+            Error::UnbuildableType { name, .. } if name == "Loop"
+        ));
+        assert!(matches!(
+            parse("type Loop = Change<Loop>;"),
+            Error::UnbuildableType { name, .. } if name == "Loop"
+        ));
+        assert!(matches!(
+            parse("type Pair<T, T> { left: T }"),
+            Error::DuplicateTypeParam { ty, param, .. } if ty == "Pair" && param == "T"
+        ));
+        assert!(matches!(
+            parse("type Index<K> { entries: {K: Int} }"),
+            Error::InvalidMapKey { name, .. } if name == "K"
+        ));
+        assert!(matches!(
+            parse("type Counter<T> { count: T = 0 }"),
+            Error::InvalidDefault { ty, .. } if ty == "T"
+        ));
+    }
+
+    #[test]
+    fn it_resolves_aggregate_identity() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount(id: Uuid) {
+                open_account(owner: String) -> OpenedAccount
+            }
+
+            aggregate Ledger keyed by account_number: AccountNumber {}
+
+            aggregate Customer keyed by email: EmailAddress {}
+
+            aggregate Branch {}
+
+            event OpenedAccount { owner: String }
+
+            scalar AccountNumber: String
+            scalar Email
+            type EmailAddress = Email
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let identity = schema.aggregates["BankAccount"].identity.as_ref().unwrap();
+        assert_eq!(identity.name, "id");
+        assert_eq!(identity.ty, TypeRef::Scalar(Scalar::Uuid));
+        assert_eq!(
+            schema.aggregates["Ledger"].identity.as_ref().unwrap().ty,
+            TypeRef::CustomScalar("AccountNumber".to_string())
+        );
+        assert_eq!(
+            schema.aggregates["Customer"].identity.as_ref().unwrap().ty,
+            TypeRef::Alias("EmailAddress".to_string())
+        );
+        assert!(schema.aggregates["Branch"].identity.is_none());
+    }
+
+    #[test]
+    fn it_rejects_invalid_aggregate_identity() {
+        let parse = |aggregate: &str| {
+            crate::parse(&format!(
+                "version = \"0.1.0\"\n\nscalar Amount: Float\ntype Key {{ id: Uuid }}\n\n{aggregate}"
+            ))
+            .unwrap_err()
+        };
+
+        for (aggregate, ty) in [
+            ("aggregate BankAccount(id: Uuid?) {}", "Uuid?"),
+            ("aggregate BankAccount(id: Float) {}", "Float"),
+            ("aggregate BankAccount(id: [String]) {}", "[String]"),
+            ("aggregate BankAccount keyed by id: Amount {}", "Amount"),
+            ("aggregate BankAccount keyed by id: Key {}", "Key"),
+        ] {
+            assert!(
+                matches!(
+                    parse(aggregate),
+                    Error::InvalidIdentity { aggregate, ty: found, .. }
+                        if aggregate == "BankAccount" && found == ty
+                ),
+                "{aggregate}"
+            );
+        }
+        assert!(matches!(
+            parse("aggregate BankAccount(id: AccountId) {}"),
+            Error::TypeNotDefined { name, .. } if name == "AccountId"
+        ));
+    }
+
+    #[test]
+    fn it_resolves_aggregate_state() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                state {
+                    balance: Float = 0
+                    status: AccountStatus
+                }
+
+                open_account(owner: String) -> OpenedAccount
+            }
+
+            aggregate Ledger {
+                record(amount: Float) -> Recorded
+            }
+
+            event OpenedAccount { owner: String }
+            event Recorded { amount: Float }
+
+            enum AccountStatus { Open, Closed }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let state = &schema.aggregates["BankAccount"].state;
+        assert_eq!(state.len(), 2);
+        assert_eq!(state["balance"].default, Some(Value::Int(0)));
+        assert_eq!(
+            state["status"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Enum(
+                "AccountStatus".to_string()
+            )))
+        );
+        assert!(schema.aggregates["BankAccount"]
+            .commands
+            .contains_key("open_account"));
+        assert!(schema.aggregates["Ledger"].state.is_empty());
+    }
+
+    #[test]
+    fn it_rejects_invalid_aggregate_state() {
+        let parse = |body: &str| {
+            crate::parse(&format!(
+                "version = \"0.1.0\"\n\naggregate BankAccount {{\n{body}\n}}"
+            ))
+            .unwrap_err()
+        };
+
+        assert!(matches!(
+            parse("state { owner: User }"),
+            Error::TypeNotDefined { name, .. } if name == "User"
+        ));
+        assert!(matches!(
+            parse("state { balance: Float, balance: Int }"),
+            Error::DuplicateStateField { aggregate, field, .. }
+                if aggregate == "BankAccount" && field == "balance"
+        ));
+        assert!(matches!(
+            parse("state { balance: Float }\nstate { status: String }"),
+            Error::DuplicateState { aggregate, position } if aggregate == "BankAccount" && position.line == 5
+        ));
+    }
+
+    #[test]
+    fn it_resolves_projections() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            /// Balance of each account.
+            projection AccountSummary {
+                owner: String
+                balance: Float = 0
+            } on (OpenedAccount, DepositedFunds)
+
+            event OpenedAccount { owner: String }
+            event DepositedFunds { amount: Float }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let projection = &schema.projections["AccountSummary"];
+        assert_eq!(projection.docs.as_deref(), Some("Balance of each account."));
+        assert_eq!(projection.fields["balance"].default, Some(Value::Int(0)));
+        assert_eq!(projection.events, vec!["OpenedAccount", "DepositedFunds"]);
+        assert_eq!(
+            schema.events[&projection.events[0]].fields["owner"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Scalar(Scalar::String)))
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_projections() {
+        let parse = |projection: &str| {
+            crate::parse(&format!(
+                "version = \"0.1.0\"\n\nevent OpenedAccount {{}}\n\n{projection}"
+            ))
+            .unwrap_err()
+        };
+
+        assert!(matches!(
+            parse("projection Summary {} on (OpenedAccount, ClosedAccount)"),
+            Error::EventNotDefined { name, .. } if name == "ClosedAccount"
+        ));
+        assert!(matches!(
+            parse("projection Summary {} on ()"),
+            Error::EmptyProjection { name, .. } if name == "Summary"
+        ));
+        assert!(matches!(
+            parse("projection Summary {} on (OpenedAccount, OpenedAccount)"),
+            Error::DuplicateSubscription { projection, event, .. }
+                if projection == "Summary" && event == "OpenedAccount"
+        ));
+        assert!(matches!(
+            parse("projection Summary { owner: String, owner: String } on (OpenedAccount)"),
+            Error::DuplicateProjectionField { projection, field, .. }
+                if projection == "Summary" && field == "owner"
+        ));
+        assert!(matches!(
+            parse("projection Summary {} on (OpenedAccount)\nprojection Summary {} on (OpenedAccount)"),
+            Error::DuplicateProjection { name, .. } if name == "Summary"
+        ));
+    }
+
+    #[test]
+    fn it_resolves_sagas() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate Sender {
+                send_funds(amount: Float) -> SentFunds
+            }
+
+            aggregate Recipient {
+                receive_funds(amount: Float) -> (ReceivedFunds | RejectedFunds)
+            }
+
+            saga TransferFunds {
+                on SentFunds -> Recipient.receive_funds
+                on ReceivedFunds -> done; on RejectedFunds -> done
+            }
+
+            event SentFunds { amount: Float }
+            event ReceivedFunds { amount: Float }
+            event RejectedFunds { amount: Float }
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let saga = &schema.sagas["TransferFunds"];
+        assert_eq!(saga.steps.len(), 3);
+        assert_eq!(saga.steps[0].event, "SentFunds");
+        assert!(matches!(
+            &saga.steps[0].target,
+            SagaTarget::Command { aggregate, command }
+                if aggregate == "Recipient" && command == "receive_funds"
+        ));
+        assert_eq!(
+            saga.steps[0].target.command(&schema),
+            Some(&schema.aggregates["Recipient"].commands["receive_funds"])
+        );
+        assert_eq!(saga.steps[1].target, SagaTarget::Done);
+        assert_eq!(saga.steps[1].target.command(&schema), None);
+
+        let next: Vec<_> = saga
+            .next_steps(&schema, &saga.steps[0])
+            .iter()
+            .map(|step| step.event.as_str())
+            .collect();
+        assert_eq!(next, vec!["ReceivedFunds", "RejectedFunds"]);
+        assert!(saga.next_steps(&schema, &saga.steps[1]).is_empty());
+    }
+
+    #[test]
+    fn it_rejects_invalid_sagas() {
+        let parse = |saga: &str| {
+            crate::parse(&format!(
+                "version = \"0.1.0\"\n\naggregate Recipient {{\n    receive_funds() -> ReceivedFunds\n}}\n\nevent SentFunds {{}}\nevent ReceivedFunds {{}}\n\n{saga}"
+            ))
+            .unwrap_err()
+        };
+
+        assert!(matches!(
+            parse("saga Transfer { on RejectedFunds -> done }"),
+            Error::EventNotDefined { name, .. } if name == "RejectedFunds"
+        ));
+        assert!(matches!(
+            parse("saga Transfer { on SentFunds -> Sender.send_funds }"),
+            Error::AggregateNotDefined { name, .. } if name == "Sender"
+        ));
+        assert!(matches!(
+            parse("saga Transfer { on SentFunds -> Recipient.refund }"),
+            Error::CommandNotDefined { aggregate, command, .. }
+                if aggregate == "Recipient" && command == "refund"
+        ));
+        assert!(matches!(
+            parse("saga Transfer {}"),
+            Error::EmptySaga { name, .. } if name == "Transfer"
+        ));
+        assert!(matches!(
+            parse("saga Transfer { on SentFunds -> done }\nsaga Transfer { on SentFunds -> done }"),
+            Error::DuplicateSaga { name, .. } if name == "Transfer"
+        ));
+    }
+
+    #[test]
+    fn it_resolves_commands_without_events() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                validate_card(number: String) -> ()
+                check_limit(amount: Float) -> ( ) ! LimitExceeded
+            }
+
+            error LimitExceeded {}
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let commands = &schema.aggregates["BankAccount"].commands;
+        assert_eq!(commands["validate_card"].events, CommandEvents::None);
+        assert_eq!(commands["check_limit"].events, CommandEvents::None);
+        assert_eq!(commands["check_limit"].errors.len(), 1);
+
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                validate_card(number: String)
+            }
+        "#;
+        assert!(matches!(
+            crate::parse(schema_str).unwrap_err(),
+            Error::Parse(_)
+        ));
+    }
+
+    #[test]
+    fn it_resolves_command_errors() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                withdraw_funds(amount: Float) -> WithdrewFunds ! (InsufficientFunds | AccountClosed)
+                deposit_funds(amount: Float) -> DepositedFunds ! AccountClosed
+                open_account() -> OpenedAccount
+            }
+
+            event WithdrewFunds { amount: Float }
+            event DepositedFunds { amount: Float }
+            event OpenedAccount {}
+
+            /// Balance is too low to withdraw the amount.
+            error InsufficientFunds {
+                balance: Float
+            }
+
+            error AccountClosed {}
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        let insufficient_funds = &schema.errors["InsufficientFunds"];
+        assert_eq!(
+            insufficient_funds.docs.as_deref(),
+            Some("Balance is too low to withdraw the amount.")
+        );
+        assert_eq!(
+            insufficient_funds.fields["balance"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Scalar(Scalar::Float)))
+        );
+
+        let commands = &schema.aggregates["BankAccount"].commands;
+        let errors: Vec<_> = commands["withdraw_funds"]
+            .errors
+            .iter()
+            .map(|error| error.name.as_str())
+            .collect();
+        assert_eq!(errors, vec!["InsufficientFunds", "AccountClosed"]);
+        assert_eq!(
+            commands["deposit_funds"].errors,
+            vec![schema.errors["AccountClosed"].clone()]
+        );
+        assert!(commands["open_account"].errors.is_empty());
+    }
+
+    #[test]
+    fn it_rejects_invalid_command_errors() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                close_account() -> ClosedAccount ! AccountFrozen
+            }
+
+            event ClosedAccount {}
+        "#;
+        assert!(matches!(
+            crate::parse(schema_str).unwrap_err(),
+            Error::ErrorNotDefined { name, .. } if name == "AccountFrozen"
+        ));
+
+        let schema_str = r#"
+            version = "0.1.0"
+
+            error AccountFrozen {}
+            error AccountFrozen { since: Timestamp }
+        "#;
+        assert!(matches!(
+            crate::parse(schema_str).unwrap_err(),
+            Error::DuplicateError { name, .. } if name == "AccountFrozen"
+        ));
+
+        let schema_str = r#"
+            version = "0.1.0"
+
+            error AccountFrozen { since: Timestamp, since: Date }
+        "#;
+        assert!(matches!(
+            crate::parse(schema_str).unwrap_err(),
+            Error::DuplicateErrorField { error, field, .. }
+                if error == "AccountFrozen" && field == "since"
+        ));
+
+        let schema_str = r#"
+            version = "0.1.0"
+
+            aggregate BankAccount {
+                withdraw() -> WithdrewFunds ! (AccountFrozen | AccountFrozen)
+            }
+
+            event WithdrewFunds {}
+            error AccountFrozen {}
+        "#;
+        assert!(matches!(
+            crate::parse(schema_str).unwrap_err(),
+            Error::DuplicateCommandError { command, error, position }
+                if command == "withdraw" && error == "AccountFrozen" && position.line == 5
+        ));
+    }
+
+    #[test]
+    fn it_allows_buildable_recursive_types() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            event OrgChartChanged {
+                root: Employee
+            }
+
+            type Employee {
+                name: String
+                manager: Employee?
+                reports: [Employee]
+                team: Team?
+            }
+
+            type Team {
+                lead: Employee
+            }
+
+            type Expr = Literal { value: Int } | Add { left: Expr, right: Expr }
+
+            type Tree = [Forest];
+            type Forest = {String: Tree};
+
+            type Maybe = Other?;
+            type Other = Maybe;
+        "#;
+
+        let schema = crate::parse(schema_str).unwrap();
+        assert_eq!(
+            schema.types["Employee"].fields["reports"].ty,
+            RepeatableType::RequiredArray(Box::new(RepeatableType::Single(TypeOpt::Required(
+                TypeRef::Custom("Employee".to_string())
+            ))))
+        );
+        assert_eq!(
+            schema.types["Team"].fields["lead"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Custom("Employee".to_string())))
+        );
+    }
+
+    #[test]
+    fn it_rejects_unbuildable_recursive_types() {
+        let schema_str = r#"
+            version = "0.1.0"
+
+            type Node {
+                next: Node
+            }
+        "#;
+        let err = crate::parse(schema_str).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::UnbuildableType { ref name, .. } if name == "Node"
+        ));
+
+        let schema_str = r#"
+            version = "0.1.0"
+
+            type Chicken {
+                egg: Egg
+            }
+
+            type Egg {
+                chicken: Chicken
+            }
+        "#;
+        let err = crate::parse(schema_str).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::UnbuildableType { ref name, .. } if name == "Chicken"
+        ));
+
+        let schema_str = r#"
+            version = "0.1.0"
+
+            type Left = Right;
+            type Right = Left;
+        "#;
+        let err = crate::parse(schema_str).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::CyclicAlias { ref name, .. } if name == "Left"
+        ));
+    }
+}
@@ -0,0 +1,549 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    ptr,
+};
+
+use typed_arena::Arena;
+
+use super::{
+    parse_string_literal, qualified_name, referenced_types, resolve_name, Position, Schema,
+};
+use crate::{
+    parser::{
+        self,
+        event::{CommandError, Event, Field},
+        import::Import,
+        types::{Alias, CustomScalar, CustomType, Enum, Union},
+    },
+    Error,
+};
+
+/// Parsed schema file along with the files it imports.
+struct Source<'a> {
+    /// Path as given to [`Schema::from_file`] or written in the import, used in errors.
+    path: PathBuf,
+    schema: parser::schema::Schema<'a>,
+    /// Canonical path of each import, in the order they are declared.
+    imports: Vec<PathBuf>,
+}
+
+/// Loads and validates a schema file with every file it imports, directly or indirectly.
+pub(super) fn load_schema(path: &Path) -> Result<Schema, Error> {
+    let canonical = fs::canonicalize(path).map_err(|err| in_file(path, err.into()))?;
+    // Parsed schemas borrow the contents of their files while further files are loaded.
+    let contents = Arena::new();
+    let mut sources = HashMap::new();
+    load_source(path, &canonical, &contents, &mut Vec::new(), &mut sources)?;
+
+    let (root, _) = sources.get_key_value(&canonical).unwrap();
+    let mut resolver = Resolver {
+        root,
+        sources: &sources,
+        resolved: HashMap::new(),
+    };
+    let schema = resolver.resolve(root)?;
+    Schema::validate_parsed_schema(schema).map_err(|err| in_file(path, err))
+}
+
+/// Reads and parses a file and the files it imports, rejecting imports which lead back to a file
+/// being loaded.
+fn load_source<'a>(
+    path: &Path,
+    canonical: &Path,
+    contents: &'a Arena<String>,
+    stack: &mut Vec<PathBuf>,
+    sources: &mut HashMap<PathBuf, Source<'a>>,
+) -> Result<(), Error> {
+    let content = fs::read_to_string(canonical).map_err(|err| in_file(path, err.into()))?;
+    let schema =
+        parser::parse(contents.alloc(content).as_str()).map_err(|err| in_file(path, err.into()))?;
+    let imports: Vec<_> = schema
+        .imports
+        .iter()
+        .map(|import| (import_path(path, import), Position::from(import.path)))
+        .collect();
+
+    stack.push(canonical.to_path_buf());
+    let mut canonical_imports = Vec::with_capacity(imports.len());
+    for (import_path, position) in imports {
+        let import_canonical =
+            fs::canonicalize(&import_path).map_err(|err| in_file(&import_path, err.into()))?;
+        if stack.contains(&import_canonical) {
+            return Err(in_file(
+                path,
+                Error::CyclicImport {
+                    path: import_path,
+                    position,
+                },
+            ));
+        }
+        if !sources.contains_key(&import_canonical) {
+            load_source(&import_path, &import_canonical, contents, stack, sources)?;
+        }
+        canonical_imports.push(import_canonical);
+    }
+    stack.pop();
+
+    sources.insert(
+        canonical.to_path_buf(),
+        Source {
+            path: path.to_path_buf(),
+            schema,
+            imports: canonical_imports,
+        },
+    );
+
+    Ok(())
+}
+
+/// Merges imported declarations into each file, once all sources are loaded.
+struct Resolver<'a> {
+    root: &'a Path,
+    sources: &'a HashMap<PathBuf, Source<'a>>,
+    resolved: HashMap<&'a Path, parser::schema::Schema<'a>>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Parsed schema of a file with its imported declarations merged in.
+    ///
+    /// Imported files are validated on their own, so errors refer to the file they occur in.
+    fn resolve(&mut self, canonical: &'a Path) -> Result<parser::schema::Schema<'a>, Error> {
+        if let Some(schema) = self.resolved.get(canonical) {
+            return Ok(schema.clone());
+        }
+
+        let source = &self.sources[canonical];
+        let mut schema = source.schema.clone();
+        let imports = std::mem::take(&mut schema.imports);
+        for (import, import_canonical) in imports.iter().zip(&source.imports) {
+            let imported = self.resolve(import_canonical)?;
+            let import_path = &self.sources[import_canonical].path;
+            import_declarations(&mut schema, import, import_path, &imported)
+                .map_err(|err| in_file(&source.path, err))?;
+        }
+
+        if canonical != self.root {
+            Schema::validate(schema.clone(), false).map_err(|err| in_file(&source.path, err))?;
+        }
+        self.resolved.insert(canonical, schema.clone());
+
+        Ok(schema)
+    }
+}
+
+/// Merges the declarations selected by an import into the importing schema.
+///
+/// Named declarations bring along the types they depend on. Declarations which are already
+/// present through another import of the same file are skipped.
+fn import_declarations<'i>(
+    schema: &mut parser::schema::Schema<'i>,
+    import: &Import<'i>,
+    path: &'i Path,
+    imported: &parser::schema::Schema<'i>,
+) -> Result<(), Error> {
+    let available = Declaration::all(imported);
+    let selected = match &import.names {
+        None => available,
+        Some(names) => {
+            let qualified_names: Vec<_> =
+                available.iter().map(Declaration::qualified_name).collect();
+            let type_names: Vec<_> = available
+                .iter()
+                .zip(&qualified_names)
+                .filter(|(ty, _)| ty.namespace() == Namespace::Types)
+                .map(|(_, name)| name.as_str())
+                .collect();
+
+            // Names are imported from the package of the imported file.
+            let package = imported.packages.first().map(|package| *package.fragment());
+            let mut pending = Vec::with_capacity(names.len());
+            for name in names {
+                let names = qualified_names.iter().map(String::as_str);
+                match resolve_name(names, package, name)? {
+                    Some(qualified) => pending.push(qualified),
+                    None => {
+                        return Err(Error::UnresolvedImport {
+                            name: name.to_string(),
+                            path: path.to_path_buf(),
+                            position: (*name).into(),
+                        })
+                    }
+                }
+            }
+
+            // Names which do not resolve are reported when the imported file is validated.
+            let mut seen = HashSet::new();
+            while let Some(name) = pending.pop() {
+                if seen.insert(name) {
+                    for (ty, _) in available
+                        .iter()
+                        .zip(&qualified_names)
+                        .filter(|(_, qualified)| *qualified == name)
+                    {
+                        let package = ty.package().map(|package| *package.fragment());
+                        for dependency in ty.dependencies() {
+                            let names = type_names.iter().copied();
+                            if let Ok(Some(qualified)) = resolve_name(names, package, &dependency) {
+                                pending.push(qualified);
+                            }
+                        }
+                    }
+                }
+            }
+
+            available
+                .into_iter()
+                .filter(|ty| seen.contains(ty.qualified_name().as_str()))
+                .collect()
+        }
+    };
+
+    for declaration in selected {
+        let duplicate = Declaration::all(schema)
+            .into_iter()
+            .find(|ty| {
+                ty.namespace() == declaration.namespace()
+                    && ty.qualified_name() == declaration.qualified_name()
+            })
+            // Each file is parsed once, so the same declaration shares its source text.
+            .map(|ty| !ptr::eq(ty.name(), declaration.name()));
+        match duplicate {
+            Some(false) => {}
+            Some(true) => {
+                let name = declaration.name();
+                let position = import
+                    .names
+                    .iter()
+                    .flatten()
+                    .find(|ident| *ident.fragment() == name)
+                    .map_or_else(|| import.path.into(), |ident| (*ident).into());
+                return Err(Error::DuplicateImport {
+                    name: name.to_string(),
+                    path: path.to_path_buf(),
+                    position,
+                });
+            }
+            None => declaration.insert_into(schema, path),
+        }
+    }
+
+    Ok(())
+}
+
+/// Declaration which can be imported from another file.
+///
+/// Aggregates, projections and sagas belong to the file declaring them, and are never imported.
+#[derive(Clone, Copy)]
+enum Declaration<'a, 'i> {
+    Event(&'a Event<'i>),
+    Error(&'a CommandError<'i>),
+    CustomType(&'a CustomType<'i>),
+    Union(&'a Union<'i>),
+    Alias(&'a Alias<'i>),
+    Enum(&'a Enum<'i>),
+    Scalar(&'a CustomScalar<'i>),
+}
+
+impl<'a, 'i> Declaration<'a, 'i> {
+    fn all(schema: &'a parser::schema::Schema<'i>) -> Vec<Self> {
+        schema
+            .events
+            .iter()
+            .map(Declaration::Event)
+            .chain(schema.errors.iter().map(Declaration::Error))
+            .chain(schema.types.iter().map(Declaration::CustomType))
+            .chain(schema.unions.iter().map(Declaration::Union))
+            .chain(schema.aliases.iter().map(Declaration::Alias))
+            .chain(schema.enums.iter().map(Declaration::Enum))
+            .chain(schema.scalars.iter().map(Declaration::Scalar))
+            .collect()
+    }
+
+    fn name(&self) -> &'i str {
+        match self {
+            Declaration::Event(ty) => ty.ident.fragment(),
+            Declaration::Error(ty) => ty.ident.fragment(),
+            Declaration::CustomType(ty) => ty.ident.fragment(),
+            Declaration::Union(ty) => ty.ident.fragment(),
+            Declaration::Alias(ty) => ty.ident.fragment(),
+            Declaration::Enum(ty) => ty.ident.fragment(),
+            Declaration::Scalar(ty) => ty.ident.fragment(),
+        }
+    }
+
+    fn package(&self) -> Option<parser::Span<'i>> {
+        match self {
+            Declaration::Event(ty) => ty.package,
+            Declaration::Error(ty) => ty.package,
+            Declaration::CustomType(ty) => ty.package,
+            Declaration::Union(ty) => ty.package,
+            Declaration::Alias(ty) => ty.package,
+            Declaration::Enum(ty) => ty.package,
+            Declaration::Scalar(ty) => ty.package,
+        }
+    }
+
+    fn qualified_name(&self) -> String {
+        qualified_name(self.package(), self.name())
+    }
+
+    /// Events, errors and user defined types are declared in separate namespaces.
+    fn namespace(&self) -> Namespace {
+        match self {
+            Declaration::Event(_) => Namespace::Events,
+            Declaration::Error(_) => Namespace::Errors,
+            _ => Namespace::Types,
+        }
+    }
+
+    /// Names of the user defined types the declaration refers to.
+    fn dependencies(&self) -> Vec<parser::Span<'i>> {
+        let fields = |fields: &[Field<'i>]| {
+            fields
+                .iter()
+                .flat_map(|field| referenced_types(&field.ty))
+                .collect()
+        };
+
+        match self {
+            Declaration::Event(ty) => fields(&ty.fields),
+            Declaration::Error(ty) => fields(&ty.fields),
+            Declaration::CustomType(ty) => fields(&ty.fields),
+            Declaration::Union(ty) => ty
+                .variants
+                .iter()
+                .flat_map(|variant| fields(&variant.fields))
+                .collect(),
+            Declaration::Alias(ty) => referenced_types(&ty.ty),
+            Declaration::Enum(_) | Declaration::Scalar(_) => vec![],
+        }
+    }
+
+    /// Inserts a copy of the declaration, recording the file it was declared in unless it was
+    /// itself imported into that file.
+    fn insert_into(self, schema: &mut parser::schema::Schema<'i>, path: &'i Path) {
+        let file = |file: Option<&'i Path>| file.or(Some(path));
+        match self {
+            Declaration::Event(ty) => schema.events.push(Event {
+                file: file(ty.file),
+                ..ty.clone()
+            }),
+            Declaration::Error(ty) => schema.errors.push(CommandError {
+                file: file(ty.file),
+                ..ty.clone()
+            }),
+            Declaration::CustomType(ty) => schema.types.push(CustomType {
+                file: file(ty.file),
+                ..ty.clone()
+            }),
+            Declaration::Union(ty) => schema.unions.push(Union {
+                file: file(ty.file),
+                ..ty.clone()
+            }),
+            Declaration::Alias(ty) => schema.aliases.push(Alias {
+                file: file(ty.file),
+                ..ty.clone()
+            }),
+            Declaration::Enum(ty) => schema.enums.push(Enum {
+                file: file(ty.file),
+                ..ty.clone()
+            }),
+            Declaration::Scalar(ty) => schema.scalars.push(CustomScalar {
+                file: file(ty.file),
+                ..ty.clone()
+            }),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Namespace {
+    Events,
+    Errors,
+    Types,
+}
+
+/// Path of an imported file, relative to the directory of the importing file.
+fn import_path(path: &Path, import: &Import) -> PathBuf {
+    path.parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(parse_string_literal(import.path.fragment()))
+}
+
+/// Attributes an error to the file it occurred in, unless it already is.
+pub(super) fn in_file(path: &Path, err: Error) -> Error {
+    match err {
+        Error::File { .. } => err,
+        err => Error::File {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::{
+        schema::{CommandEvents, EventOpt, RepeatableType, Schema, TypeOpt, TypeRef},
+        Error,
+    };
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/imports")
+            .join(name)
+    }
+
+    /// Source error along with the file it occurred in.
+    fn file_error(err: Error) -> (PathBuf, Error) {
+        match err {
+            Error::File { path, source } => (path, *source),
+            err => panic!("expected file error, found {err:?}"),
+        }
+    }
+
+    #[test]
+    fn it_resolves_imports() {
+        let schema = Schema::from_file(fixture("service.esdl")).unwrap();
+
+        let mut types: Vec<_> = schema.types.keys().map(String::as_str).collect();
+        types.sort_unstable();
+        assert_eq!(types, vec!["Money", "User"]);
+        assert!(schema.enums.contains_key("Currency"));
+        assert!(schema.events.contains_key("AccountOpened"));
+        assert!(!schema.events.contains_key("AccountClosed"));
+        assert!(schema.errors.contains_key("AccountFrozen"));
+        assert_eq!(
+            schema.aggregates["BankAccount"].commands["open_account"].errors[0].name,
+            "AccountFrozen"
+        );
+    }
+
+    #[test]
+    fn it_records_the_files_of_imported_declarations() {
+        let schema = Schema::from_file(fixture("service.esdl")).unwrap();
+        let file = |file: &Option<PathBuf>| file.clone().unwrap();
+        assert!(file(&schema.types["Money"].file).ends_with("shared/types.esdl"));
+        assert_eq!(schema.types["Money"].position.line, 3);
+        assert!(file(&schema.enums["Currency"].file).ends_with("shared/currency.esdl"));
+        assert!(file(&schema.events["AccountOpened"].file).ends_with("shared/events.esdl"));
+
+        let schema = Schema::from_file(fixture("same_names.esdl")).unwrap();
+        assert_eq!(schema.types["banking.accounts.Money"].file, None);
+
+        let (path, err) =
+            file_error(Schema::from_file(fixture("ambiguous_import.esdl")).unwrap_err());
+        assert!(path.ends_with("shared/invoice.esdl"));
+        assert!(matches!(
+            err,
+            Error::AmbiguousName { name, position } if name == "Money" && position.line == 6
+        ));
+    }
+
+    #[test]
+    fn it_qualifies_imported_names_by_package() {
+        let schema = Schema::from_file(fixture("packaged.esdl")).unwrap();
+        assert_eq!(schema.package.as_deref(), Some("banking.accounts"));
+        assert_eq!(schema.events["banking.accounts.Opened"].name, "Opened");
+        assert_eq!(schema.types["common.Money"].name, "Money");
+        assert_eq!(
+            schema.events["banking.accounts.Opened"].fields["currency"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Enum(
+                "common.Currency".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn it_separates_same_names_by_package() {
+        let schema = Schema::from_file(fixture("same_names.esdl")).unwrap();
+
+        let mut events: Vec<_> = schema.events.keys().map(String::as_str).collect();
+        events.sort_unstable();
+        assert_eq!(events, vec!["banking.accounts.Opened", "common.Opened"]);
+        let mut types: Vec<_> = schema.types.keys().map(String::as_str).collect();
+        types.sort_unstable();
+        assert_eq!(types, vec!["banking.accounts.Money", "common.Money"]);
+
+        let opened = &schema.events["banking.accounts.Opened"];
+        assert_eq!(
+            opened.fields["a"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Custom(
+                "common.Money".to_string()
+            )))
+        );
+        assert_eq!(
+            opened.fields["b"].ty,
+            RepeatableType::Single(TypeOpt::Required(TypeRef::Custom(
+                "banking.accounts.Money".to_string()
+            )))
+        );
+        assert!(matches!(
+            &schema.aggregates["BankAccount"].commands["open"].events,
+            CommandEvents::Single(EventOpt::Required(event))
+                if event.qualified_name == "banking.accounts.Opened"
+        ));
+
+        let (path, err) = file_error(Schema::from_file(fixture("ambiguous.esdl")).unwrap_err());
+        assert!(path.ends_with("ambiguous.esdl"));
+        assert!(matches!(
+            err,
+            Error::AmbiguousName { name, position } if name == "Money" && position.line == 7
+        ));
+    }
+
+    #[test]
+    fn it_rejects_cyclic_imports() {
+        let (path, err) = file_error(Schema::from_file(fixture("cyclic.esdl")).unwrap_err());
+        assert!(path.ends_with("cycle/b.esdl"));
+        assert!(
+            matches!(err, Error::CyclicImport { path, position } if path.ends_with("a.esdl") && position.line == 1)
+        );
+    }
+
+    #[test]
+    fn it_rejects_unresolved_imports() {
+        let (path, err) = file_error(Schema::from_file(fixture("unresolved.esdl")).unwrap_err());
+        assert!(path.ends_with("unresolved.esdl"));
+        assert!(matches!(
+            err,
+            Error::UnresolvedImport { name, path, position }
+                if name == "Wallet" && path.ends_with("shared/types.esdl") && position.column == 17
+        ));
+    }
+
+    #[test]
+    fn it_rejects_duplicate_imports() {
+        let (path, err) = file_error(Schema::from_file(fixture("duplicate.esdl")).unwrap_err());
+        assert!(path.ends_with("duplicate.esdl"));
+        assert!(matches!(
+            err,
+            Error::DuplicateImport { name, path, position }
+                if name == "Money" && path.ends_with("shared/types.esdl") && position.line == 3
+        ));
+    }
+
+    #[test]
+    fn it_reports_errors_in_imported_files() {
+        let (path, err) = file_error(Schema::from_file(fixture("broken.esdl")).unwrap_err());
+        assert!(path.ends_with("shared/broken.esdl"));
+        assert!(matches!(
+            err,
+            Error::TypeNotDefined { name, position } if name == "Wallet" && position.line == 2
+        ));
+
+        let (path, err) = file_error(Schema::from_file(fixture("missing.esdl")).unwrap_err());
+        assert!(path.ends_with("shared/missing.esdl"));
+        assert!(matches!(err, Error::Io(_)));
+    }
+
+    #[test]
+    fn it_requires_a_file_for_imports() {
+        let err = crate::parse("version = \"0.1.0\"\nimport \"shared/types.esdl\"").unwrap_err();
+        assert!(matches!(err, Error::UnsupportedImport { position } if position.line == 2));
+    }
+}